@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use robotics_lib::world::environmental_conditions::WeatherType;
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use crate::world_file::{parse_content_value, WorldFile, DEFAULT_PROGRESSION};
+
+
+// Capacity of the containers when the legend doesn't say it.
+static DEFAULT_CAPACITY:usize=10;
+
+
+// Small worlds written by hand, for the tests of the path finding and of the decisions of the robot.
+// The characters are the same of the terminal view. First the tiles, then (after an empty line)
+// the contents with "." for nothing and "@" for the robot, then the legend for the quantities:
+//
+//      .....
+//      .&&&.
+//      .....
+//
+//      @...g
+//      .....
+//      ..B..
+//
+//      legend g=Garbage(3)
+//      legend B=Bin(0..5)
+//
+// Tiles: "~" deep water, "-" shallow water, ":" sand, "." grass, "=" street, "n" hill, "^" mountain,
+// "#" snow, "&" lava, "W" wall, "O" teleport, "o" teleport not discovered, "?" unknown (only for the robot map).
+// Contents: "r" rock, "t" tree, "g" garbage, "f" fire, "c" coin, "B" bin, "C" crate, "$" bank,
+// "w" water, "M" market, "p" fish, "b" bush. Without legend the quantity is 1 (containers 0..10).
+#[derive(Debug,Clone)]
+pub struct AsciiWorld{
+    // Like the robot map ("?" are the None), so it can be given directly to the planners
+    pub(crate) tiles:Vec<Vec<Option<Tile>>>,
+    pub(crate) spawn:(usize,usize),
+}
+
+impl AsciiWorld{
+
+    pub(crate) fn parse(text:&str)->Result<AsciiWorld,String>{
+        let mut sections:Vec<Vec<&str>>=vec![Vec::new()];
+        let mut legend=HashMap::new();
+
+        for line in text.lines(){
+            let line=line.trim();
+            if let Some(entry)=line.strip_prefix("legend"){
+                let (c,content)=entry.trim().split_once('=').ok_or(format!("wrong legend \"{}\"",line))?;
+                let c=c.trim().chars().next().ok_or(format!("wrong legend \"{}\"",line))?;
+                legend.insert(c,parse_content_value(content.trim())?);
+            }else if line.is_empty(){
+                if !sections.last().unwrap().is_empty(){
+                    sections.push(Vec::new());
+                }
+            }else{
+                sections.last_mut().unwrap().push(line);
+            }
+        }
+
+        let rows=&sections[0];
+        let size=rows.len();
+        if size==0{
+            return Err(String::from("the world is empty"));
+        }
+
+        let mut tiles=Vec::new();
+        for (i,row) in rows.iter().enumerate(){
+            if row.chars().count()!=size{
+                return Err(format!("row {} has {} tiles instead of {} (the world is a square)",i,row.chars().count(),size));
+            }
+            let row=row.chars().map(|c| match c{
+                '?' => Ok(Option::None),
+                c => tile_type(c).map(|tile_type| Some(Tile{tile_type,content:Content::None,elevation:0})),
+            }).collect::<Result<Vec<Option<Tile>>,String>>().map_err(|e| format!("row {}: {}",i,e))?;
+            tiles.push(row);
+        }
+
+        let mut spawn=Option::None;
+        if let Some(contents)=sections.get(1).filter(|s| !s.is_empty()){
+            if contents.len()!=size{
+                return Err(format!("{} rows of contents instead of {}",contents.len(),size));
+            }
+
+            for (i,row) in contents.iter().enumerate(){
+                if row.chars().count()!=size{
+                    return Err(format!("row {} of contents has {} characters instead of {}",i,row.chars().count(),size));
+                }
+                for (j,c) in row.chars().enumerate(){
+                    let content=match c{
+                        '.' => continue,
+                        '@' => {
+                            spawn=Some((i,j));
+                            continue;
+                        }
+                        c => match legend.get(&c){
+                            Some(content) => content.clone(),
+                            Option::None => content(c).map_err(|e| format!("row {} of contents: {}",i,e))?,
+                        },
+                    };
+
+                    match tiles[i][j].as_mut(){
+                        Some(tile) => tile.content=content,
+                        Option::None => return Err(format!("content on an unknown tile ({},{})",i,j)),
+                    }
+                }
+            }
+        }
+
+        Ok(AsciiWorld{
+            tiles,
+            spawn:spawn.unwrap_or((0,0)),
+        })
+    }
+
+    // A full world, for the runner (WorldFile is a Generator). All the tiles must be known.
+    pub(crate) fn world_file(&self)->Result<WorldFile,String>{
+        let tiles=self.tiles.iter().enumerate()
+            .map(|(i,row)| row.iter().enumerate()
+                .map(|(j,t)| t.clone().ok_or(format!("unknown tile ({},{}) in a world",i,j)))
+                .collect::<Result<Vec<Tile>,String>>())
+            .collect::<Result<Vec<Vec<Tile>>,String>>()?;
+
+        Ok(WorldFile{
+            tiles,
+            spawn:self.spawn,
+            progression:DEFAULT_PROGRESSION,
+            hour:8,
+            weather:vec![WeatherType::Sunny],
+            max_score:0.0,
+            score_table:Option::None,
+        })
+    }
+}
+
+
+fn tile_type(c:char)->Result<TileType,String>{
+    match c{
+        '~' => Ok(TileType::DeepWater),
+        '-' => Ok(TileType::ShallowWater),
+        ':' => Ok(TileType::Sand),
+        '.' => Ok(TileType::Grass),
+        '=' => Ok(TileType::Street),
+        'n' => Ok(TileType::Hill),
+        '^' => Ok(TileType::Mountain),
+        '#' => Ok(TileType::Snow),
+        '&' => Ok(TileType::Lava),
+        'W' => Ok(TileType::Wall),
+        'O' => Ok(TileType::Teleport(true)),
+        'o' => Ok(TileType::Teleport(false)),
+        other => Err(format!("unknown tile \"{}\"",other)),
+    }
+}
+
+fn content(c:char)->Result<Content,String>{
+    match c{
+        'r' => Ok(Content::Rock(1)),
+        't' => Ok(Content::Tree(1)),
+        'g' => Ok(Content::Garbage(1)),
+        'f' => Ok(Content::Fire),
+        'c' => Ok(Content::Coin(1)),
+        'B' => Ok(Content::Bin(0..DEFAULT_CAPACITY)),
+        'C' => Ok(Content::Crate(0..DEFAULT_CAPACITY)),
+        '$' => Ok(Content::Bank(0..DEFAULT_CAPACITY)),
+        'w' => Ok(Content::Water(1)),
+        'M' => Ok(Content::Market(1)),
+        'p' => Ok(Content::Fish(1)),
+        'b' => Ok(Content::Bush(1)),
+        other => Err(format!("unknown content \"{}\"",other)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests{
+    use std::sync::Arc;
+    use robotics_lib::world::tile::{Content, TileType};
+    use crate::container_registry::ContainerRegistry;
+    use crate::genetic_algorithm::{evolve, Action, InputDir};
+    use crate::market::Markets;
+    use crate::simulator::Simulator;
+    use crate::tour_planner::{tour_stops, StopKind, TourProblem};
+    use crate::weather_forecast::StepForecast;
+    use super::AsciiWorld;
+
+    // A wall of lava between the robot and the place it wants to reach.
+    static LAVA:&str="
+        .....
+        ..&..
+        ..&..
+        ..&..
+        .....
+
+        .....
+        .....
+        .@...
+        .....
+        .....
+    ";
+
+    // Garbage next to the robot and a bin below it.
+    static DEPOSIT:&str="
+        .....
+        .....
+        .....
+        .....
+        .....
+
+        .@g..
+        .....
+        ..B..
+        .....
+        .....
+
+        legend B=Bin(0..5)
+    ";
+
+    #[test]
+    fn parse_tiles_contents_and_legend(){
+        let world=AsciiWorld::parse(DEPOSIT).unwrap();
+        assert_eq!(world.tiles.len(),5);
+        assert_eq!(world.spawn,(0,1));
+        assert_eq!(world.tiles[1][2].as_ref().unwrap().tile_type,TileType::Grass);
+        assert_eq!(world.tiles[0][2].as_ref().unwrap().content,Content::Garbage(1));
+        assert_eq!(world.tiles[2][2].as_ref().unwrap().content,Content::Bin(0..5));
+        assert!(world.world_file().unwrap().environment().is_ok());
+
+        assert!(AsciiWorld::parse("..\n.x").is_err());
+        assert!(AsciiWorld::parse("..\n.").is_err());
+        //A world for the runner can't have unknown tiles
+        assert!(AsciiWorld::parse("..\n.?").unwrap().world_file().is_err());
+    }
+
+    #[test]
+    fn lava_is_walked_around(){
+        let world=AsciiWorld::parse(LAVA).unwrap();
+        let simulator=Simulator::new(&world.tiles);
+        assert_eq!(world.spawn,(2,1));
+
+        let straight=simulator.replay(&[Action::Move(InputDir::Right),Action::Move(InputDir::Right)],world.spawn,0);
+        assert!(straight.steps[0].blocked);
+        assert_eq!(straight.steps[1].position,(2,1));
+
+        let detour=[InputDir::Bottom,InputDir::Bottom,InputDir::Right,InputDir::Right,InputDir::Top,InputDir::Top];
+        let genome:Vec<Action>=detour.iter().map(|d| Action::Move(*d)).collect();
+        let trace=simulator.replay(&genome,world.spawn,0);
+
+        assert!(trace.steps.iter().all(|s| !s.blocked));
+        assert_eq!(trace.steps.last().unwrap().position,(2,3));
+        assert_eq!(trace.total_cost(),6*TileType::Grass.properties().cost());
+    }
+
+    #[test]
+    fn the_genetic_algorithm_goes_around_the_lava(){
+        let world=AsciiWorld::parse(LAVA).unwrap();
+        let map=Arc::new(world.tiles.clone());
+        let forecast=Arc::new(StepForecast::default());
+        let backpack=Arc::new(Vec::new());
+        let teleports=Arc::new(Vec::new());
+        let target=(2,3);
+
+        //Like the threads of the robot, we try again if a search doesn't arrive
+        let best=(0..5)
+            .map(|_| evolve(&map,world.spawn,target,&Option::None,&forecast,&backpack,&teleports))
+            .min_by_key(|g| (g.distanze_from_dest,g.weight))
+            .unwrap();
+        assert_eq!(best.distanze_from_dest,0);
+
+        let trace=Simulator::new(&world.tiles).replay(&best.vector,world.spawn,0);
+        assert_eq!(trace.steps.last().unwrap().position,target);
+        for step in trace.steps.iter(){
+            let (x,y)=step.position;
+            assert_ne!(world.tiles[x][y].as_ref().unwrap().tile_type,TileType::Lava,"{:?}",best.vector);
+        }
+    }
+
+    #[test]
+    fn garbage_is_collected_and_put_in_the_bin(){
+        let world=AsciiWorld::parse(DEPOSIT).unwrap();
+        let (x,y)=world.spawn;
+
+        let mut containers=ContainerRegistry::default();
+        containers.observe((2,2),&Content::Bin(0..5));
+
+        //The tour takes the garbage before going to the bin
+        let stops=tour_stops(&world.tiles,&containers,&Markets::default(),x,y,&|c| c.to_default()==Content::Garbage(0),&|_| false);
+        let problem=TourProblem::new(world.spawn,stops,10,Default::default(),1000);
+        let visits=problem.solve();
+
+        assert_eq!(visits.len(),2);
+        assert_eq!(problem.stops[visits[0].stop].kind,StopKind::Collect(Content::Garbage(1)));
+        assert_eq!(problem.stops[visits[1].stop].position,(2,2));
+        assert_eq!(visits[1].put,Some((Content::Garbage(0),1)));
+
+        //And the simulator agrees that the actions can be done
+        let genome=[
+            Action::Destroy(InputDir::Right),
+            Action::Move(InputDir::Bottom),
+            Action::Move(InputDir::Bottom),
+            Action::Put(InputDir::Right,Content::Garbage(0),1),
+        ];
+        let trace=Simulator::new(&world.tiles).replay(&genome,world.spawn,0);
+
+        assert!(trace.steps.iter().all(|s| !s.blocked));
+        assert_eq!(trace.steps[0].collected,Some(Content::Garbage(1)));
+        assert_eq!(trace.steps[3].deposited,Some((Content::Garbage(0),1)));
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use oxagaudiotool::OxAgAudioTool;
+use oxagaudiotool::sound_config::OxAgSoundConfig;
+
+
+// File we write with "--audio file".
+pub static AUDIO_FILE:&str="feedback.wav";
+
+// Folder with the sounds we play with "--audio device".
+static SOUNDS_FOLDER:&str="sounds";
+
+static SAMPLE_RATE:u32=22050;
+
+// Silence after every sound in the file, so we can tell them apart.
+static SILENCE_MS:u32=80;
+
+// Under this energy we tell that the energy is low (only once, until it goes up again).
+pub static LOW_ENERGY:usize=150;
+
+
+// The things we want to hear while the robot runs.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum Cue{
+    PlanFound,
+    Deposit,
+    LowEnergy,
+    Stuck,
+}
+
+impl Cue{
+    // Frequency (Hz) and length (ms) of the tone we write in the file.
+    fn tone(&self)->(f32,u32){
+        match self{
+            Cue::PlanFound => (660.0,120),
+            Cue::Deposit => (880.0,200),
+            Cue::LowEnergy => (220.0,400),
+            Cue::Stuck => (330.0,300),
+        }
+    }
+
+    fn sound_file(&self)->String{
+        let name=match self{
+            Cue::PlanFound => "plan",
+            Cue::Deposit => "deposit",
+            Cue::LowEnergy => "low_energy",
+            Cue::Stuck => "stuck",
+        };
+        format!("{}/{}.ogg",SOUNDS_FOLDER,name)
+    }
+}
+
+
+// Where the sounds go. With "File" we don't need a sound device (useful for the runs without a screen).
+pub enum AudioOutput{
+    Off,
+    Device(OxAgAudioTool,HashMap<Cue,OxAgSoundConfig>),
+    File(Vec<i16>),
+}
+
+
+pub struct AudioFeedback{
+    output:AudioOutput,
+    low_energy:bool,
+}
+
+impl Default for AudioFeedback{
+    fn default() -> Self {
+        AudioFeedback{
+            output:AudioOutput::Off,
+            low_energy:false,
+        }
+    }
+}
+
+impl AudioFeedback{
+
+    // The output is chosen with "--audio off|device|file". Without it we don't play anything.
+    pub(crate) fn from_args()->AudioFeedback{
+        let args:Vec<String>=std::env::args().collect();
+        let mode=match args.iter().position(|a| a=="--audio"){
+            Some(i) if i+1<args.len() => args[i+1].clone(),
+            _ => String::from("off"),
+        };
+
+        let output=match mode.as_str(){
+            "file" => AudioOutput::File(Vec::new()),
+            "device" => match OxAgAudioTool::new(HashMap::new(),HashMap::new(),HashMap::new()){
+                Ok(tool) => {
+                    let sounds=[Cue::PlanFound,Cue::Deposit,Cue::LowEnergy,Cue::Stuck].into_iter()
+                        .map(|c| (c,OxAgSoundConfig::new(&c.sound_file())))
+                        .collect();
+                    AudioOutput::Device(tool,sounds)
+                }
+                Err(e) => {
+                    println!("No sound device ({:?}), we write the sounds in {}",e,AUDIO_FILE);
+                    AudioOutput::File(Vec::new())
+                }
+            },
+            _ => AudioOutput::Off,
+        };
+
+        AudioFeedback{output,low_energy:false}
+    }
+
+    pub(crate) fn play(&mut self,cue:Cue){
+        match &mut self.output{
+            AudioOutput::Off => {}
+            AudioOutput::Device(tool,sounds) => {
+                if let Some(sound)=sounds.get(&cue){
+                    if let Err(e)=tool.play_audio(sound){
+                        println!("Error playing {:?}:{:?}",cue,e);
+                    }
+                }
+            }
+            AudioOutput::File(samples) => {
+                let (frequency,ms)=cue.tone();
+                push_tone(samples,frequency,ms);
+                push_tone(samples,0.0,SILENCE_MS);
+            }
+        }
+    }
+
+    // We play LowEnergy when the energy goes under LOW_ENERGY, not every tick.
+    pub(crate) fn observe_energy(&mut self,level:usize){
+        if level<LOW_ENERGY && !self.low_energy{
+            self.low_energy=true;
+            self.play(Cue::LowEnergy);
+        }else if level>=LOW_ENERGY{
+            self.low_energy=false;
+        }
+    }
+
+    pub(crate) fn save(&self,filename:&str){
+        if let AudioOutput::File(samples)=&self.output{
+            if let Err(e)=write_wav(filename,samples){
+                println!("Error writing {}:{}",filename,e);
+            }
+        }
+    }
+}
+
+
+// A sine tone with a short fade at the ends (frequency 0 is silence).
+fn push_tone(samples:&mut Vec<i16>,frequency:f32,ms:u32){
+    let n=(SAMPLE_RATE*ms/1000) as usize;
+    let fade=(n/10).max(1);
+
+    for i in 0..n{
+        let t=i as f32/SAMPLE_RATE as f32;
+        let envelope=(i.min(n-1-i) as f32/fade as f32).min(1.0);
+        let value=(2.0*std::f32::consts::PI*frequency*t).sin()*envelope*0.5;
+        samples.push((value*i16::MAX as f32) as i16);
+    }
+}
+
+// Mono PCM, 16 bit.
+fn write_wav(filename:&str,samples:&[i16])->std::io::Result<()>{
+    let mut file=BufWriter::new(File::create(filename)?);
+    let data_size=(samples.len()*2) as u32;
+
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36+data_size).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    file.write_all(&(SAMPLE_RATE*2).to_le_bytes())?;
+    file.write_all(&2u16.to_le_bytes())?;
+    file.write_all(&16u16.to_le_bytes())?;
+
+    file.write_all(b"data")?;
+    file.write_all(&data_size.to_le_bytes())?;
+    for s in samples{
+        file.write_all(&s.to_le_bytes())?;
+    }
+    file.flush()
+}
@@ -0,0 +1,207 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+
+// Where we write the table of the batch.
+pub static BENCHMARK_FILE:&str="benchmark.csv";
+
+// Folder with a subfolder for every run (the files of the run and its output).
+static BENCHMARK_FOLDER:&str="benchmark";
+
+// Ticks of every run if nobody says it.
+static DEFAULT_TICKS:usize=2000;
+
+// Arguments we give to every run as they are.
+static FORWARDED:[&str;3]=["--generator","--size","--variation"];
+
+
+// What a single run did. The robot updates it, main adds the planning time.
+#[derive(Debug,Clone,Default)]
+pub struct RunStats{
+    pub(crate) ticks:usize,
+    pub(crate) tiles_discovered:usize,
+    pub(crate) collected:usize,
+    pub(crate) deposited:usize,
+    pub(crate) energy_spent:usize,
+    pub(crate) planning_ms:u128,
+    pub(crate) failures:usize,
+    // Why the run stopped
+    pub(crate) end:String,
+}
+
+impl RunStats{
+    fn header()->&'static str{
+        "ticks;tiles_discovered;collected;deposited;energy_spent;planning_ms;failures;end"
+    }
+
+    fn to_line(&self)->String{
+        format!("{};{};{};{};{};{};{};{}",self.ticks,self.tiles_discovered,self.collected,self.deposited,self.energy_spent,self.planning_ms,self.failures,self.end)
+    }
+
+    fn parse(line:&str)->Option<RunStats>{
+        let p:Vec<&str>=line.trim().split(';').collect();
+        if p.len()!=8{return Option::None}
+        Some(RunStats{
+            ticks:p[0].parse().ok()?,
+            tiles_discovered:p[1].parse().ok()?,
+            collected:p[2].parse().ok()?,
+            deposited:p[3].parse().ok()?,
+            energy_spent:p[4].parse().ok()?,
+            planning_ms:p[5].parse().ok()?,
+            failures:p[6].parse().ok()?,
+            end:p[7].to_string(),
+        })
+    }
+
+    // The run writes its line here at the end ("--summary <file>").
+    pub(crate) fn save(&self,filename:&str){
+        if let Err(e)=fs::write(filename,format!("{}\n{}\n",RunStats::header(),self.to_line())){
+            println!("Error writing the summary {}:{}",filename,e);
+        }
+    }
+}
+
+
+// Arguments of a single run: how many ticks it can do and where it writes the summary.
+pub(crate) fn tick_budget()->Option<usize>{
+    argument("--ticks").and_then(|t| t.parse().ok())
+}
+
+pub(crate) fn summary_file()->Option<String>{
+    argument("--summary")
+}
+
+pub(crate) fn is_batch()->bool{
+    std::env::args().any(|a| a=="--batch")
+}
+
+fn argument(flag:&str)->Option<String>{
+    let args:Vec<String>=std::env::args().collect();
+    match args.iter().position(|a| a==flag){
+        Some(i) if i+1<args.len() => Some(args[i+1].clone()),
+        _ => Option::None,
+    }
+}
+
+
+// One run of the batch.
+#[derive(Debug,Clone)]
+struct BatchRun{
+    seed:u64,
+    config:Option<PathBuf>,
+    folder:PathBuf,
+}
+
+
+// "--batch --seeds 1,2,3 --configs a.conf,b.conf --ticks 2000 --jobs 4"
+// We run this program again for every seed and config (the robot uses globals, so one process for every run),
+// at most "jobs" at the same time, and we write a line for every run in BENCHMARK_FILE.
+pub(crate) fn run_batch(){
+    let seeds:Vec<u64>=argument("--seeds").map(|s| s.split(',').filter_map(|v| v.trim().parse().ok()).collect()).unwrap_or(vec![0]);
+    let configs:Vec<Option<PathBuf>>=match argument("--configs"){
+        Some(c) => {
+            //A config we can't find would run with the default one, and the table would lie
+            let mut configs=Vec::new();
+            for f in c.split(',').map(|f| f.trim()){
+                match fs::canonicalize(f){
+                    Ok(path) => configs.push(Some(path)),
+                    Err(e) => {
+                        println!("Error with the config \"{}\":{}",f,e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            configs
+        }
+        Option::None => vec![Option::None],
+    };
+    let ticks=tick_budget().unwrap_or(DEFAULT_TICKS);
+    let jobs=argument("--jobs").and_then(|j| j.parse().ok()).unwrap_or(2usize).max(1);
+
+    let mut runs=Vec::new();
+    for config in configs.iter(){
+        for seed in seeds.iter(){
+            let folder=PathBuf::from(BENCHMARK_FOLDER).join(format!("run_{}",runs.len()));
+            runs.push(BatchRun{seed:*seed,config:config.clone(),folder});
+        }
+    }
+
+    println!("Batch of {} runs, {} ticks each, {} at the same time",runs.len(),ticks,jobs);
+
+    let mut waiting:Vec<usize>=(0..runs.len()).rev().collect();
+    let mut running:Vec<(usize,Child)>=Vec::new();
+    let mut results:Vec<Option<RunStats>>=vec![Option::None;runs.len()];
+
+    while !waiting.is_empty() || !running.is_empty(){
+        while running.len()<jobs{
+            let index=match waiting.pop(){
+                Some(i) => i,
+                Option::None => break,
+            };
+            match start_run(&runs[index],ticks){
+                Ok(child) => running.push((index,child)),
+                Err(e) => println!("Error starting the run {}:{}",index,e),
+            }
+        }
+
+        let mut i=0;
+        while i<running.len(){
+            match running[i].1.try_wait(){
+                Ok(Option::None) => i+=1,
+                _ => {
+                    let (index,_)=running.remove(i);
+                    let summary=runs[index].folder.join("summary.csv");
+                    results[index]=fs::read_to_string(&summary).ok().and_then(|t| t.lines().nth(1).and_then(RunStats::parse));
+                    println!("Run {} finished",index);
+                }
+            }
+        }
+        sleep(Duration::from_millis(200));
+    }
+
+    write_report(&runs,&results);
+}
+
+fn start_run(run:&BatchRun,ticks:usize)->Result<Child,String>{
+    fs::create_dir_all(&run.folder).map_err(|e| e.to_string())?;
+    let output=File::create(run.folder.join("output.txt")).map_err(|e| e.to_string())?;
+
+    let mut command=Command::new(std::env::current_exe().map_err(|e| e.to_string())?);
+    command.current_dir(&run.folder)
+        .args(["--seed",&run.seed.to_string(),"--ticks",&ticks.to_string(),"--summary","summary.csv"])
+        .stdout(Stdio::from(output))
+        .stderr(Stdio::null());
+
+    if let Some(config)=&run.config{
+        command.arg("--config").arg(config);
+    }
+    for flag in FORWARDED{
+        if let Some(value)=argument(flag){
+            command.args([flag,&value]);
+        }
+    }
+
+    command.spawn().map_err(|e| e.to_string())
+}
+
+// The table in BENCHMARK_FILE, and a short version on the screen.
+fn write_report(runs:&[BatchRun],results:&[Option<RunStats>]){
+    let mut csv=format!("run;seed;config;{}\n",RunStats::header());
+    println!("{:>4} {:>8} {:>7} {:>7} {:>9} {:>9} {:>7} {:>9} {:>8}  {}","run","seed","ticks","tiles","collected","deposited","energy","plan_ms","failures","end");
+
+    for (i,(run,result)) in runs.iter().zip(results.iter()).enumerate(){
+        let config=run.config.as_ref().map(|c| c.display().to_string()).unwrap_or(String::from("default"));
+        let stats=result.clone().unwrap_or(RunStats{end:String::from("crashed"),..RunStats::default()});
+
+        csv.push_str(&format!("{};{};{};{}\n",i,run.seed,config,stats.to_line()));
+        println!("{:>4} {:>8} {:>7} {:>7} {:>9} {:>9} {:>7} {:>9} {:>8}  {}",i,run.seed,stats.ticks,stats.tiles_discovered,stats.collected,stats.deposited,stats.energy_spent,stats.planning_ms,stats.failures,stats.end);
+    }
+
+    match fs::write(BENCHMARK_FILE,csv){
+        Ok(_) => println!("Benchmark written in {}",BENCHMARK_FILE),
+        Err(e) => println!("Error writing {}:{}",BENCHMARK_FILE,e),
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use robotics_lib::world::tile::Content;
+use crate::coverage::Goal;
+use crate::world_source::WorldSource;
+
+
+// File we read if nobody tells us another one with "--config <file>".
+pub static CONFIG_FILE:&str="robot.conf";
+
+
+// Which contents the robot collects and where it puts them.
+// Example of the configuration (one "key=value" for every line, "#" for comments):
+//
+//      collect=coin,garbage,tree
+//      ignore=rock
+//      target.garbage=bin
+//      threshold.garbage=5
+//      threshold.default=6
+//      batch.garbage=5
+//      priority=garbage,tree,coin
+//      sell=rock,fish
+//      goal=coverage
+//
+// "threshold" is how many elements we want in the backpack before going to the container,
+// "batch" is the max quantity we put every time, "priority" is the order we use to empty the backpack,
+// "sell" are the contents we can sell in the markets, "goal" is what we do when there is nothing else to do (see coverage).
+#[derive(Debug,Clone)]
+pub struct Policy{
+    collect:HashSet<Content>,
+    ignore:HashSet<Content>,
+    targets:HashMap<Content,Content>,
+    thresholds:HashMap<Content,usize>,
+    default_threshold:usize,
+    batches:HashMap<Content,usize>,
+    priority:Vec<Content>,
+    sell:HashSet<Content>,
+}
+
+impl Default for Policy{
+    // What the robot always did: garbage in the bins, trees in the crates, coins in the banks,
+    // and we start to empty the backpack when we have more than 5 of something (garbage first).
+    fn default() -> Self {
+        Policy{
+            collect:HashSet::from([Content::Coin(0),Content::Garbage(0),Content::Tree(0)]),
+            ignore:HashSet::new(),
+            targets:HashMap::from([
+                (Content::Tree(0),Content::Crate(0..0)),
+                (Content::Garbage(0),Content::Bin(0..0)),
+                (Content::Coin(0),Content::Bank(0..0)),
+            ]),
+            thresholds:HashMap::from([(Content::Garbage(0),6)]),
+            default_threshold:6,
+            batches:HashMap::new(),
+            priority:vec![Content::Garbage(0)],
+            sell:HashSet::from([Content::Rock(0),Content::Fish(0)]),
+        }
+    }
+}
+
+impl Policy{
+
+    pub(crate) fn collects(&self,content:&Content)->bool{
+        let c=content.to_default();
+        self.collect.contains(&c) && !self.ignore.contains(&c)
+    }
+
+    pub(crate) fn ignores(&self,content:&Content)->bool{
+        self.ignore.contains(&content.to_default())
+    }
+
+    // Container where we put the content, Content::None if we keep it.
+    pub(crate) fn container_for(&self,content:&Content)->Content{
+        match self.targets.get(&content.to_default()){
+            Some(container) => container.clone(),
+            Option::None => Content::None,
+        }
+    }
+
+    pub(crate) fn is_target_container(&self,content:&Content)->bool{
+        let c=content.to_default();
+        self.targets.values().any(|t| *t==c)
+    }
+
+    pub(crate) fn threshold(&self,content:&Content)->usize{
+        *self.thresholds.get(&content.to_default()).unwrap_or(&self.default_threshold)
+    }
+
+    pub(crate) fn batch(&self,content:&Content)->Option<usize>{
+        self.batches.get(&content.to_default()).cloned()
+    }
+
+    pub(crate) fn sells(&self,content:&Content)->bool{
+        self.sell.contains(&content.to_default())
+    }
+
+    // Lower is more important. The contents without a priority come after all the others.
+    pub(crate) fn priority(&self,content:&Content)->usize{
+        let c=content.to_default();
+        self.priority.iter().position(|p| *p==c).unwrap_or(self.priority.len())
+    }
+
+    fn set(&mut self,key:&str,value:&str)->Result<(),String>{
+        match key.split_once('.'){
+            Some(("target",content)) => {
+                let container=parse_content(value)?;
+                self.targets.insert(parse_content(content)?,container);
+            }
+            Some(("threshold","default")) => {
+                self.default_threshold=parse_number(value)?;
+            }
+            Some(("threshold",content)) => {
+                self.thresholds.insert(parse_content(content)?,parse_number(value)?);
+            }
+            Some(("batch",content)) => {
+                self.batches.insert(parse_content(content)?,parse_number(value)?);
+            }
+            _ => match key{
+                "collect" => self.collect=parse_list(value)?.into_iter().collect(),
+                "ignore" => self.ignore=parse_list(value)?.into_iter().collect(),
+                "priority" => self.priority=parse_list(value)?,
+                "sell" => self.sell=parse_list(value)?.into_iter().collect(),
+                _ => return Err(format!("unknown key \"{}\"",key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(Debug,Clone,Default)]
+pub struct Config{
+    pub(crate) policy:Policy,
+    pub(crate) goal:Goal,
+    // Keys starting with "world.", see world_source
+    pub(crate) world:WorldSource,
+}
+
+impl Config{
+
+    // The file is given with "--config <file>", otherwise we use CONFIG_FILE.
+    // If there is no file we use the default values. The world and the goal can be changed also with the arguments.
+    pub(crate) fn load()->Config{
+        let args:Vec<String>=std::env::args().collect();
+        let filename=match args.iter().position(|a| a=="--config"){
+            Some(i) if i+1<args.len() => args[i+1].clone(),
+            _ => CONFIG_FILE.to_string(),
+        };
+
+        let mut config=match fs::read_to_string(&filename){
+            Ok(text) => {
+                let config=Config::parse(&text);
+                println!("Configuration loaded from {}:{:?}",filename,config.policy);
+                config
+            }
+            Err(_) => Config::default(),
+        };
+
+        config.world.apply_args(&args);
+        if let Some(i)=args.iter().position(|a| a=="--goal"){
+            match args.get(i+1).map(|g| Goal::parse(g)){
+                Some(Ok(goal)) => config.goal=goal,
+                Some(Err(e)) => println!("Argument --goal: {}",e),
+                Option::None => println!("Argument --goal: missing value"),
+            }
+        }
+        config
+    }
+
+    // The wrong lines are printed and ignored.
+    pub(crate) fn parse(text:&str)->Config{
+        let mut config=Config::default();
+
+        for (n,line) in text.lines().enumerate(){
+            let line=line.split('#').next().unwrap().trim();
+            if line.is_empty(){continue}
+
+            let result=match line.split_once('='){
+                Some((key,value)) => match key.trim().strip_prefix("world."){
+                    Some(key) => config.world.set(key,value.trim()),
+                    Option::None if key.trim()=="goal" => Goal::parse(value).map(|goal| config.goal=goal),
+                    Option::None => config.policy.set(key.trim(),value.trim()),
+                },
+                Option::None => Err(String::from("missing \"=\"")),
+            };
+
+            if let Err(e)=result{
+                println!("Configuration, line {}: {}",n+1,e);
+            }
+        }
+        config
+    }
+}
+
+
+pub(crate) fn parse_content(name:&str)->Result<Content,String>{
+    match name.trim().to_lowercase().as_str(){
+        "rock" => Ok(Content::Rock(0)),
+        "tree" => Ok(Content::Tree(0)),
+        "garbage" => Ok(Content::Garbage(0)),
+        "fire" => Ok(Content::Fire),
+        "coin" => Ok(Content::Coin(0)),
+        "bin" => Ok(Content::Bin(0..0)),
+        "crate" => Ok(Content::Crate(0..0)),
+        "bank" => Ok(Content::Bank(0..0)),
+        "water" => Ok(Content::Water(0)),
+        "market" => Ok(Content::Market(0)),
+        "fish" => Ok(Content::Fish(0)),
+        "bush" => Ok(Content::Bush(0)),
+        "none" => Ok(Content::None),
+        other => Err(format!("unknown content \"{}\"",other)),
+    }
+}
+
+fn parse_list(value:&str)->Result<Vec<Content>,String>{
+    value.split(',').filter(|v| !v.trim().is_empty()).map(parse_content).collect()
+}
+
+pub(crate) fn parse_number(value:&str)->Result<usize,String>{
+    value.parse().map_err(|_| format!("\"{}\" is not a number",value))
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use robotics_lib::world::tile::Content;
+
+
+// After this many failed puts we consider the container full, even if the map says something else.
+static MAX_FAILED_PUTS:usize=2;
+
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct ContainerInfo{
+    // Kind of container (default content, like Bin(0..0))
+    pub(crate) kind:Content,
+    // How many elements we think we can still put in it
+    pub(crate) capacity:usize,
+    // Last capacity we saw on the map. If it doesn't change, the map is older than our puts.
+    last_observed:usize,
+    pub(crate) failed_puts:usize,
+}
+
+impl ContainerInfo{
+    pub(crate) fn is_usable(&self)->bool{
+        self.capacity>0 && self.failed_puts<MAX_FAILED_PUTS
+    }
+}
+
+
+// All the containers we know, with the capacity we estimate from the map and from the results of our puts.
+pub struct ContainerRegistry{
+    containers:HashMap<(usize,usize),ContainerInfo>,
+}
+
+impl Default for ContainerRegistry{
+    fn default() -> Self {
+        ContainerRegistry{
+            containers:HashMap::new(),
+        }
+    }
+}
+
+impl ContainerRegistry{
+
+    // We saw the content of the tile on the map.
+    pub(crate) fn observe(&mut self,position:(usize,usize),content:&Content){
+        let observed=match content{
+            Content::Bin(r) | Content::Crate(r) | Content::Bank(r) => r.end-r.start,
+            _ => {
+                //The container isn't there anymore
+                self.containers.remove(&position);
+                return;
+            }
+        };
+
+        match self.containers.get_mut(&position){
+            Some(info) if info.last_observed==observed => {
+                //Nothing new from the map, we keep our estimate
+            }
+            Some(info) => {
+                info.capacity=observed;
+                info.last_observed=observed;
+            }
+            Option::None => {
+                self.containers.insert(position,ContainerInfo{
+                    kind:content.to_default(),
+                    capacity:observed,
+                    last_observed:observed,
+                    failed_puts:0,
+                });
+            }
+        }
+    }
+
+    // Result of a put in the container: how many elements went in it, or an error.
+    pub(crate) fn record_put(&mut self,position:(usize,usize),result:Result<usize,()>){
+        if let Some(info)=self.containers.get_mut(&position){
+            match result{
+                Ok(quantity) => {
+                    info.capacity=info.capacity.saturating_sub(quantity);
+                    info.failed_puts=0;
+                }
+                Err(_) => {
+                    info.failed_puts+=1;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn exists(&self,kind:&Content)->bool{
+        self.containers.values().any(|c| c.kind==kind.to_default() && c.is_usable())
+    }
+
+    pub(crate) fn all(&self)->impl Iterator<Item=(&(usize,usize),&ContainerInfo)>{
+        self.containers.iter()
+    }
+
+    // A container we knew from a previous run. The map will correct it when we see it again.
+    pub(crate) fn restore(&mut self,position:(usize,usize),kind:Content,capacity:usize,failed_puts:usize){
+        self.containers.insert(position,ContainerInfo{
+            kind:kind.to_default(),
+            capacity,
+            last_observed:capacity,
+            failed_puts,
+        });
+    }
+
+    pub(crate) fn usable(&self)->impl Iterator<Item=(&(usize,usize),&ContainerInfo)>{
+        self.containers.iter().filter(|(_,c)| c.is_usable())
+    }
+
+    // Where we put "quantity" elements, starting from "from".
+    // We prefer the closest container that can take everything, otherwise we split the load
+    // going every time to the closest container from where we are.
+    pub(crate) fn plan_deposit(&self,kind:&Content,quantity:usize,from:(usize,usize))->Vec<((usize,usize),usize)>{
+        let mut candidates:Vec<(&(usize,usize),&ContainerInfo)>=self.usable().filter(|(_,c)| c.kind==kind.to_default()).collect();
+
+        let whole=candidates.iter()
+            .filter(|(_,c)| c.capacity>=quantity)
+            .min_by_key(|(p,_)| distance(**p,from));
+
+        if let Some((p,_))=whole{
+            return vec![(**p,quantity)];
+        }
+
+        let mut result=Vec::new();
+        let mut left=quantity;
+        let mut position=from;
+
+        while left>0 && !candidates.is_empty(){
+            let (index,_)=candidates.iter().enumerate().min_by_key(|(_,(p,_))| distance(**p,position)).unwrap();
+            let (p,c)=candidates.remove(index);
+
+            let put=left.min(c.capacity);
+            result.push((*p,put));
+
+            left-=put;
+            position=*p;
+        }
+        result
+    }
+
+    pub(crate) fn report(&self)->String{
+        let mut s=String::from("Containers:");
+        for (p,c) in self.containers.iter(){
+            s.push_str(&format!("\n({},{}) {:?} capacity:{} failed puts:{}",p.0,p.1,c.kind,c.capacity,c.failed_puts));
+        }
+        s
+    }
+}
+
+
+fn distance(a:(usize,usize),b:(usize,usize))->usize{
+    a.0.abs_diff(b.0)+a.1.abs_diff(b.1)
+}
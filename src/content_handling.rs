@@ -0,0 +1,71 @@
+use robotics_lib::world::tile::Content;
+use crate::CONFIG;
+
+
+// We keep some rocks in the backpack, so we can build roads.
+pub static COLLECT_ROCKS_FOR_ROADS:bool=true;
+
+// How many rocks we want to have for the roads.
+pub static ROCK_RESERVE:usize=10;
+
+
+// What the robot does with a content it finds.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum ContentUse{
+    // We destroy it to put it in the backpack
+    Collect,
+    // We don't walk there (fire without water)
+    Avoid,
+    // We put water on it (fire)
+    Extinguish,
+    Ignore,
+}
+
+
+pub(crate) fn quantity_in(backpack:&[(Content,usize)],content:&Content)->usize{
+    backpack.iter()
+        .filter(|(c,_)| c.to_default()==content.to_default())
+        .map(|(_,q)| *q)
+        .sum()
+}
+
+// It depends on the policy and on what we have in the backpack.
+pub(crate) fn content_use(content:&Content,backpack:&[(Content,usize)])->ContentUse{
+    let policy=&CONFIG.policy;
+
+    if *content!=Content::Fire && policy.ignores(content){
+        return ContentUse::Ignore;
+    }
+
+    match content{
+        Content::Fire => {
+            if quantity_in(backpack,&Content::Water(0))>0{
+                ContentUse::Extinguish
+            }else{
+                ContentUse::Avoid
+            }
+        }
+        //Bushes and fish are useful only if we can put them somewhere or sell them
+        Content::Bush(_) | Content::Fish(_) => {
+            if policy.collects(content) || policy.sells(content) || policy.container_for(content)!=Content::None{
+                ContentUse::Collect
+            }else{
+                ContentUse::Ignore
+            }
+        }
+        Content::Rock(_) => {
+            if policy.collects(content) || (COLLECT_ROCKS_FOR_ROADS && quantity_in(backpack,content)<ROCK_RESERVE){
+                ContentUse::Collect
+            }else{
+                ContentUse::Ignore
+            }
+        }
+        _ => {
+            if policy.collects(content){
+                ContentUse::Collect
+            }else{
+                ContentUse::Ignore
+            }
+        }
+    }
+}
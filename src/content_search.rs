@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use robotics_lib::world::tile::Content;
+
+
+// Radius of the first search, it doubles every time we don't find anything.
+static FIRST_SEARCH_DISTANCE:usize=10;
+static MAX_SEARCH_DISTANCE:usize=40;
+
+// Ticks we wait before searching again the same content.
+static SEARCH_COOLDOWN:usize=30;
+
+// How many tiles the tool skips while sensing (1 is every tile).
+pub static SEARCH_GRANULARITY:usize=3;
+
+
+// State of the search of one kind of content.
+#[derive(Debug,Clone)]
+struct SearchState{
+    distance:usize,
+    last_tick:usize,
+    failures:usize,
+}
+
+
+// It decides when and how far we search a content we don't know where to find (like a container),
+// so we don't waste all the energy sensing the same area again.
+pub struct ContentSearch{
+    tick:usize,
+    searches:HashMap<Content,SearchState>,
+}
+
+impl Default for ContentSearch{
+    fn default() -> Self {
+        ContentSearch{
+            tick:0,
+            searches:HashMap::new(),
+        }
+    }
+}
+
+impl ContentSearch{
+
+    pub(crate) fn next_tick(&mut self){
+        self.tick+=1;
+    }
+
+    // The radius of the next search, None if we have searched it too recently.
+    pub(crate) fn next_search(&self,content:&Content)->Option<usize>{
+        match self.searches.get(&content.to_default()){
+            Some(s) if self.tick<s.last_tick+SEARCH_COOLDOWN*(s.failures+1) => Option::None,
+            Some(s) => Some(s.distance),
+            Option::None => Some(FIRST_SEARCH_DISTANCE),
+        }
+    }
+
+    // "found" is how many tiles with the content the tool gave us.
+    pub(crate) fn record(&mut self,content:&Content,distance:usize,found:usize){
+        let tick=self.tick;
+        let state=self.searches.entry(content.to_default()).or_insert(SearchState{
+            distance,
+            last_tick:tick,
+            failures:0,
+        });
+
+        state.last_tick=tick;
+        if found>0{
+            state.distance=FIRST_SEARCH_DISTANCE;
+            state.failures=0;
+        }else{
+            state.distance=(distance*2).min(MAX_SEARCH_DISTANCE);
+            state.failures+=1;
+        }
+    }
+}
+
+
+// Energy we think a search of this radius costs: the tool senses a square, one tile every "granularity".
+pub(crate) fn search_cost(distance:usize)->usize{
+    let side=(2*distance+1)/SEARCH_GRANULARITY+1;
+    side*side/4+3
+}
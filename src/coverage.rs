@@ -0,0 +1,235 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::Write;
+use robotics_lib::world::tile::Tile;
+use crate::helpers_functions::is_good_tile;
+use crate::stuck_detector::known_tiles;
+use crate::{DISTANCE, ONE_DIRECTION_DISTANCE};
+
+
+// What the robot does when it has nothing to collect or to deposit.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Goal{
+    // The greedy exploration of the threads (PositionToGo)
+    Explore,
+    // We map the whole world region by region
+    Coverage,
+}
+
+// Used if the configuration doesn't say it ("goal=coverage" or "--goal coverage").
+pub static DEFAULT_GOAL:Goal=Goal::Explore;
+
+impl Default for Goal{
+    fn default() -> Self {
+        DEFAULT_GOAL
+    }
+}
+
+impl Goal{
+    pub(crate) fn parse(name:&str)->Result<Goal,String>{
+        match name.trim().to_lowercase().as_str(){
+            "explore" => Ok(Goal::Explore),
+            "coverage" => Ok(Goal::Coverage),
+            other => Err(format!("unknown goal \"{}\" (explore or coverage)",other)),
+        }
+    }
+}
+
+// File where we write the coverage over time.
+pub static COVERAGE_FILE:&str="coverage.csv";
+
+// Side of the square regions in which we split the map.
+static REGION_SIZE:usize=32;
+
+// A region is done when we know this percentage of its tiles.
+static REGION_DONE:f32=0.9;
+
+// Distance between two lanes of the sweep, so the views of two lanes touch.
+static LANE_SPACING:usize=2*DISTANCE+1;
+
+
+// It plans a boustrophedon sweep: the regions are visited row by row, changing direction every row,
+// and inside a region the robot walks lanes, changing direction every lane.
+pub struct CoveragePlanner{
+    tick:usize,
+    waypoints:VecDeque<(usize,usize)>,
+    // Regions we have already planned. We plan them again only if we discovered something new.
+    planned:HashSet<(usize,usize)>,
+    known_when_planned:usize,
+    // (tick, percentage of the known map)
+    pub(crate) history:Vec<(usize,f32)>,
+}
+
+impl Default for CoveragePlanner{
+    fn default() -> Self {
+        CoveragePlanner{
+            tick:0,
+            waypoints:VecDeque::new(),
+            planned:HashSet::new(),
+            known_when_planned:0,
+            history:Vec::new(),
+        }
+    }
+}
+
+impl CoveragePlanner{
+
+    pub(crate) fn next_tick(&mut self){
+        self.tick+=1;
+    }
+
+    pub(crate) fn record(&mut self,map:&Vec<Vec<Option<Tile>>>)->f32{
+        let total=map.len()*map.len();
+        let coverage=if total==0{0.0}else{known_tiles(map) as f32/total as f32};
+
+        self.history.push((self.tick,coverage));
+        coverage
+    }
+
+    // Next point where the robot has to go, already moved on a known tile where we can walk.
+    pub(crate) fn next_waypoint(&mut self,map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize)->Option<(usize,usize)>{
+        loop{
+            while let Some(w)=self.waypoints.pop_front(){
+                //Someone else has already seen around it
+                if unknown_around(map,w.0,w.1,DISTANCE)==0{continue}
+
+                match closest_walkable(map,w){
+                    Some(p) if p!=(x,y) => return Some(p),
+                    _ => {}
+                }
+            }
+
+            let region=self.next_region(map,x,y)?;
+            self.planned.insert(region);
+            self.waypoints=sweep(region,map.len());
+        }
+    }
+
+    // The incomplete region closest to ours in the boustrophedon order.
+    fn next_region(&mut self,map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize)->Option<(usize,usize)>{
+        let regions=regions_per_side(map.len());
+        if regions==0{return Option::None}
+
+        let incomplete:Vec<(usize,usize)>=(0..regions).flat_map(|r| (0..regions).map(move |c| (r,c)))
+            .filter(|r| region_coverage(map,*r)<REGION_DONE)
+            .collect();
+
+        let mut candidates:Vec<&(usize,usize)>=incomplete.iter().filter(|r| !self.planned.contains(r)).collect();
+
+        //We tried all of them. We try again only if we have discovered something since then.
+        if candidates.is_empty(){
+            let known=known_tiles(map);
+            if known<=self.known_when_planned{return Option::None}
+
+            self.known_when_planned=known;
+            self.planned.clear();
+            candidates=incomplete.iter().collect();
+        }
+
+        let current=snake_index((x/REGION_SIZE,y/REGION_SIZE),regions);
+        candidates.iter()
+            .min_by_key(|r| {
+                let index=snake_index(***r,regions);
+                (index.abs_diff(current),r.0.abs_diff(x/REGION_SIZE)+r.1.abs_diff(y/REGION_SIZE))
+            })
+            .map(|r| **r)
+    }
+
+    // We write the coverage over time, one line for every time we recorded it.
+    pub(crate) fn save(&self,filename:&str){
+        match File::create(filename){
+            Ok(mut f) => {
+                let _=writeln!(f,"tick;coverage");
+                for (tick,coverage) in self.history.iter(){
+                    let _=writeln!(f,"{};{:.4}",tick,coverage);
+                }
+            }
+            Err(e) => {println!("Error writing the coverage:{:?}",e);}
+        }
+    }
+}
+
+
+fn regions_per_side(size:usize)->usize{
+    (size+REGION_SIZE-1)/REGION_SIZE
+}
+
+// Position of the region in the boustrophedon order: even rows from left to right, odd rows from right to left.
+fn snake_index(region:(usize,usize),regions:usize)->usize{
+    if region.0%2==0{
+        region.0*regions+region.1
+    }else{
+        region.0*regions+(regions-1-region.1)
+    }
+}
+
+fn region_bounds(region:(usize,usize),size:usize)->((usize,usize),(usize,usize)){
+    let from=(region.0*REGION_SIZE,region.1*REGION_SIZE);
+    let to=(((region.0+1)*REGION_SIZE).min(size),((region.1+1)*REGION_SIZE).min(size));
+    (from,to)
+}
+
+fn region_coverage(map:&Vec<Vec<Option<Tile>>>,region:(usize,usize))->f32{
+    let ((from_x,from_y),(to_x,to_y))=region_bounds(region,map.len());
+    let total=(to_x-from_x)*(to_y-from_y);
+    if total==0{return 1.0}
+
+    let mut known=0;
+    for i in from_x..to_x{
+        for j in from_y..to_y{
+            if map[i][j].is_some(){known+=1}
+        }
+    }
+    known as f32/total as f32
+}
+
+// The lanes of the region, one every LANE_SPACING rows, walked in alternate directions.
+fn sweep(region:(usize,usize),size:usize)->VecDeque<(usize,usize)>{
+    let ((from_x,from_y),(to_x,to_y))=region_bounds(region,size);
+    let mut waypoints=VecDeque::new();
+
+    let mut left_to_right=true;
+    let mut lane=from_x+DISTANCE.min(to_x-from_x-1);
+    while lane<to_x{
+        let mut columns:Vec<usize>=(from_y..to_y).step_by(ONE_DIRECTION_DISTANCE).collect();
+        if !left_to_right{columns.reverse()}
+
+        for c in columns{
+            waypoints.push_back((lane,c));
+        }
+
+        left_to_right=!left_to_right;
+        lane+=LANE_SPACING;
+    }
+    waypoints
+}
+
+fn unknown_around(map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize,distance:usize)->usize{
+    let mut unknown=0;
+    for i in x.saturating_sub(distance)..=(x+distance).min(map.len()-1){
+        for j in y.saturating_sub(distance)..=(y+distance).min(map.len()-1){
+            if map[i][j].is_none(){unknown+=1}
+        }
+    }
+    unknown
+}
+
+// The known tile where we can walk closest to the target, so we can go there even if we haven't seen the target.
+fn closest_walkable(map:&Vec<Vec<Option<Tile>>>,target:(usize,usize))->Option<(usize,usize)>{
+    for radius in 0..REGION_SIZE{
+        let mut best:Option<(usize,usize)>=Option::None;
+
+        for i in target.0.saturating_sub(radius)..=(target.0+radius).min(map.len()-1){
+            for j in target.1.saturating_sub(radius)..=(target.1+radius).min(map.len()-1){
+                //Only the border of the square, the inside was checked with the smaller radius
+                if i.abs_diff(target.0)!=radius && j.abs_diff(target.1)!=radius{continue}
+                if is_good_tile(&map[i][j]) && best.is_none(){
+                    best=Some((i,j));
+                }
+            }
+        }
+
+        if best.is_some(){return best}
+    }
+    Option::None
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use robotics_lib::interface::Direction;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use crate::genetic_algorithm::Action;
+use crate::sensing_planner::SensorCall;
+use crate::simulator::{move_cost, Simulator, TELEPORT_COST};
+use crate::ONE_DIRECTION_DISTANCE;
+
+
+// How many samples we keep for every kind of action. The older ones are forgotten,
+// so the model follows the changes of the world (weather, time of the day).
+static MAX_SAMPLES:usize=50;
+
+// Our guess of the recharge, until we observe the real one.
+static DEFAULT_RECHARGE_PER_TICK:usize=10;
+
+// Our guess of the cost of every tile of the one_direction_view, until we observe the real one.
+pub(crate) static VIEW_COST_PER_TILE:usize=3;
+
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum ActionKind{
+    Move,
+    Destroy,
+    Put,
+    Spotlight,
+    OneDirection,
+    Teleport,
+}
+
+impl ActionKind{
+    fn from_sensor(call:&SensorCall)->ActionKind{
+        match call{
+            SensorCall::Spotlight(_) => ActionKind::Spotlight,
+            SensorCall::OneDirection(_,_) => ActionKind::OneDirection,
+        }
+    }
+}
+
+
+#[derive(Debug,Clone,Copy)]
+struct EnergySample{
+    predicted:usize,
+    actual:usize,
+}
+
+
+// It predicts how much energy every action costs, and it learns from the real energy we spent.
+// Every kind of action has its own correction (actual/predicted), so if a tool tells us a wrong
+// cost (like the spotlight) we fix it with what we observe.
+pub struct EnergyModel{
+    samples:HashMap<ActionKind,Vec<EnergySample>>,
+    recharges:Vec<usize>,
+    // Distance and energy spent of the one_direction_views, the cost per tile is fitted on them
+    views:Vec<(usize,usize)>,
+}
+
+impl Default for EnergyModel{
+    fn default() -> Self {
+        EnergyModel{
+            samples:HashMap::new(),
+            recharges:Vec::new(),
+            views:Vec::new(),
+        }
+    }
+}
+
+impl EnergyModel{
+
+    // Ratio between what we spent and what we predicted. 1.0 if we don't know anything yet.
+    pub(crate) fn correction(&self,kind:ActionKind)->f32{
+        match self.samples.get(&kind){
+            Some(v) if !v.is_empty() => {
+                let predicted:usize=v.iter().map(|s| s.predicted).sum();
+                let actual:usize=v.iter().map(|s| s.actual).sum();
+                if predicted==0{
+                    1.0
+                }else{
+                    actual as f32/predicted as f32
+                }
+            }
+            _ => 1.0,
+        }
+    }
+
+    fn corrected(&self,kind:ActionKind,raw:usize)->usize{
+        (raw as f32*self.correction(kind)).ceil() as usize
+    }
+
+    pub(crate) fn predict_move(&self,map:&Vec<Vec<Option<Tile>>>,from:(usize,usize),to:(usize,usize),environment:&EnvironmentalConditions)->usize{
+        match move_cost(map,from,to,Some(environment)){
+            Some(c) => self.corrected(ActionKind::Move,c),
+            //We don't know the tile. We can't really move there.
+            Option::None => 0,
+        }
+    }
+
+    pub(crate) fn predict_destroy(&self,content:&Content)->usize{
+        self.corrected(ActionKind::Destroy,content.properties().cost())
+    }
+
+    pub(crate) fn predict_put(&self,content:&Content,quantity:usize)->usize{
+        self.corrected(ActionKind::Put,content.properties().cost()*quantity)
+    }
+
+    pub(crate) fn predict_teleport(&self)->usize{
+        self.corrected(ActionKind::Teleport,TELEPORT_COST)
+    }
+
+    // The cost of the one_direction_view grows with the distance (the cost per tile is learned), the spotlight one is given by the tool.
+    pub(crate) fn predict_sense(&self,call:&SensorCall,tool_cost:usize)->usize{
+        let raw=match call{
+            SensorCall::Spotlight(_) => tool_cost,
+            SensorCall::OneDirection(_,distance) => (*distance as f32*self.view_cost_per_tile()).ceil() as usize,
+        };
+        self.corrected(ActionKind::from_sensor(call),raw)
+    }
+
+    // Least squares of cost=distance*k on the views we did.
+    pub(crate) fn view_cost_per_tile(&self)->f32{
+        let squares:usize=self.views.iter().map(|(d,_)| d*d).sum();
+        if squares==0{
+            return VIEW_COST_PER_TILE as f32;
+        }
+        self.views.iter().map(|(d,a)| d*a).sum::<usize>() as f32/squares as f32
+    }
+
+    // Cost of a whole path created by the threads (or by the charted path).
+    pub(crate) fn predict_path(&self,path:&[Action],x:usize,y:usize,map:&Vec<Vec<Option<Tile>>>,environment:&EnvironmentalConditions)->usize{
+        self.predict_steps(path,x,y,map,environment).iter().map(|s| s.0).sum()
+    }
+
+    // Cost of every action of the path, with the position of the robot after it.
+    // The simulator replays the path, we only correct the costs with what we learned.
+    pub(crate) fn predict_steps(&self,path:&[Action],x:usize,y:usize,map:&Vec<Vec<Option<Tile>>>,environment:&EnvironmentalConditions)->Vec<(usize,(usize,usize))>{
+        let trace=Simulator::new(map).with_environment(Some(environment)).with_sense_cost(self.predict_view()).replay(path,(x,y),0);
+
+        trace.steps.iter().map(|s|{
+            let cost=match s.action{
+                Action::Move(_) => self.corrected(ActionKind::Move,s.cost),
+                Action::Destroy(_) => self.corrected(ActionKind::Destroy,s.cost),
+                Action::Put(_,_,_) => self.corrected(ActionKind::Put,s.cost),
+                //Already predicted by the model
+                Action::Sense => s.cost,
+                Action::Teleport(_) => self.corrected(ActionKind::Teleport,s.cost),
+                Action::Wait => 0,
+            };
+            (cost,s.position)
+        }).collect()
+    }
+
+    // The sense of the paths is a one_direction_view of ONE_DIRECTION_DISTANCE.
+    pub(crate) fn predict_view(&self)->usize{
+        self.predict_sense(&SensorCall::OneDirection(Direction::Up,ONE_DIRECTION_DISTANCE),0)
+    }
+
+    // Energy we get back for every tick we wait.
+    pub(crate) fn recharge_per_tick(&self)->usize{
+        if self.recharges.is_empty(){
+            DEFAULT_RECHARGE_PER_TICK
+        }else{
+            (self.recharges.iter().sum::<usize>()/self.recharges.len()).max(1)
+        }
+    }
+
+    pub(crate) fn observe_recharge(&mut self,amount:usize){
+        if amount==0{return}
+
+        self.recharges.push(amount);
+        if self.recharges.len()>MAX_SAMPLES{
+            self.recharges.remove(0);
+        }
+    }
+
+    // How many ticks we need to wait to have "needed" energy, starting from "level".
+    pub(crate) fn ticks_to_recharge(&self,level:usize,needed:usize)->usize{
+        if level>=needed{
+            0
+        }else{
+            (needed-level+self.recharge_per_tick()-1)/self.recharge_per_tick()
+        }
+    }
+
+    // We save what we predicted and what we really spent.
+    // energy_before and energy_after are the levels of the robot around the action.
+    pub(crate) fn observe(&mut self,kind:ActionKind,predicted:usize,energy_before:usize,energy_after:usize){
+        //If the level didn't go down the action failed (or we recharged), it doesn't tell us anything.
+        //Without a prediction (unknown tile or environment) there is nothing to correct.
+        if energy_after>=energy_before || predicted==0{return}
+
+        // We save the raw prediction, otherwise the correction would be applied two times.
+        let correction=self.correction(kind);
+        let raw=if correction>0.0{(predicted as f32/correction).round() as usize}else{predicted};
+
+        let v=self.samples.entry(kind).or_insert(Vec::new());
+        v.push(EnergySample{predicted:raw,actual:energy_before-energy_after});
+
+        if v.len()>MAX_SAMPLES{
+            v.remove(0);
+        }
+    }
+
+    // The sensors also fit the cost of the one_direction_view on its distance.
+    pub(crate) fn observe_sense(&mut self,call:&SensorCall,predicted:usize,energy_before:usize,energy_after:usize){
+        if let SensorCall::OneDirection(_,distance)=call{
+            if energy_after<energy_before && *distance>0{
+                self.views.push((*distance,energy_before-energy_after));
+                if self.views.len()>MAX_SAMPLES{
+                    self.views.remove(0);
+                }
+            }
+        }
+        self.observe(ActionKind::from_sensor(call),predicted,energy_before,energy_after);
+    }
+
+    // Mean absolute percentage error of the corrected predictions for this kind of action.
+    pub(crate) fn calibration_error(&self,kind:ActionKind)->Option<f32>{
+        let v=self.samples.get(&kind)?;
+        if v.is_empty(){return Option::None}
+
+        let correction=self.correction(kind);
+        let sum:f32=v.iter().map(|s|{
+            (s.predicted as f32*correction-s.actual as f32).abs()/(s.actual.max(1)) as f32
+        }).sum();
+
+        Some(sum/v.len() as f32)
+    }
+
+    pub(crate) fn report(&self)->String{
+        let mut s=String::from("Energy model calibration:");
+        for kind in [ActionKind::Move,ActionKind::Destroy,ActionKind::Put,ActionKind::Spotlight,ActionKind::OneDirection,ActionKind::Teleport]{
+            match self.calibration_error(kind){
+                Some(e) => {
+                    s.push_str(&format!(" {:?}(samples:{}, error:{:.1}%, correction:{:.2})",kind,self.samples[&kind].len(),e*100.0,self.correction(kind)));
+                }
+                Option::None => {}
+            }
+        }
+        s
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use robotics_lib::event::events::Event;
+use robotics_lib::world::tile::Content;
+
+
+// File where we append the events of the run.
+pub static EVENT_LOG_FILE:&str="events.log";
+
+// We write on the disk only after this many events, so we don't open the file every tick.
+static FLUSH_EVERY:usize=200;
+
+
+// All the events we received, with the tick in which they arrived.
+pub struct EventLog{
+    tick:usize,
+    pending:Vec<String>,
+    // How many times we have received every kind of event.
+    counters:HashMap<String,usize>,
+    // Totals of what entered and left the backpack during the run, for the summary (keys are the default contents).
+    // What is in the backpack now is BACKPACK in main, updated with the same events.
+    collected:HashMap<Content,usize>,
+    removed:HashMap<Content,usize>,
+}
+
+impl Default for EventLog{
+    fn default() -> Self {
+        EventLog{
+            tick:0,
+            pending:Vec::new(),
+            counters:HashMap::new(),
+            collected:HashMap::new(),
+            removed:HashMap::new(),
+        }
+    }
+}
+
+impl EventLog{
+
+    pub(crate) fn next_tick(&mut self){
+        self.tick+=1;
+    }
+
+    pub(crate) fn record(&mut self,event:&Event){
+        let name=event_name(event);
+        *self.counters.entry(name.to_string()).or_insert(0)+=1;
+
+        match event{
+            Event::AddedToBackpack(content,quantity) => {
+                *self.collected.entry(content.to_default()).or_insert(0)+=quantity;
+            }
+            Event::RemovedFromBackpack(content,quantity) => {
+                *self.removed.entry(content.to_default()).or_insert(0)+=quantity;
+            }
+            _ => {}
+        }
+
+        self.pending.push(format!("{};{};{:?}",self.tick,name,event));
+
+        if self.pending.len()>=FLUSH_EVERY{
+            self.flush(EVENT_LOG_FILE);
+        }
+    }
+
+    // We append the events we haven't written yet.
+    pub(crate) fn flush(&mut self,filename:&str){
+        if self.pending.is_empty(){return}
+
+        let file=OpenOptions::new().create(true).append(true).open(filename);
+        match file{
+            Ok(mut f) => {
+                for line in self.pending.iter(){
+                    let _=writeln!(f,"{}",line);
+                }
+                self.pending.clear();
+            }
+            Err(e) => {println!("Error writing the event log:{:?}",e);}
+        }
+    }
+
+    pub(crate) fn summary(&self)->String{
+        let mut s=format!("Events until tick {}:",self.tick);
+        for (name,n) in self.counters.iter(){
+            s.push_str(&format!(" {}:{}",name,n));
+        }
+        for (content,n) in self.collected.iter(){
+            s.push_str(&format!("\nCollected {}:{}",content,n));
+        }
+        for (content,n) in self.removed.iter(){
+            s.push_str(&format!("\nRemoved from the backpack {}:{}",content,n));
+        }
+        s
+    }
+}
+
+
+pub(crate) fn event_name(event:&Event)->&'static str{
+    match event{
+        Event::Ready => "Ready",
+        Event::Terminated => "Terminated",
+        Event::TimeChanged(_) => "TimeChanged",
+        Event::DayChanged(_) => "DayChanged",
+        Event::EnergyRecharged(_) => "EnergyRecharged",
+        Event::EnergyConsumed(_) => "EnergyConsumed",
+        Event::Moved(_,_) => "Moved",
+        Event::TileContentUpdated(_,_) => "TileContentUpdated",
+        Event::AddedToBackpack(_,_) => "AddedToBackpack",
+        Event::RemovedFromBackpack(_,_) => "RemovedFromBackpack",
+    }
+}
@@ -1,27 +1,29 @@
-use rand::{Rng, thread_rng};
-use crate::{INFINITE, INPUT_DIR_SIZE};
+use rand::Rng;
+use crate::planner_rng::planner_rng;
+use crate::{DISTANCE, GENERATION_LIMIT, INFINITE, INPUT_DIR_SIZE, POPULATION_NUMBER};
 use robotics_lib::interface::Direction;
-use std::collections::HashSet;
-use robotics_lib::world::tile::Tile;
+use robotics_lib::world::tile::{Content, Tile};
 use std::sync::Arc;
 use charting_tools::charted_coordinate::ChartedCoordinate;
-use robotics_lib::utils::calculate_cost_go_with_environment;
-use robotics_lib::world::tile::Content::{Coin, Garbage, Tree};
 use robotics_lib::world::tile::TileType::ShallowWater;
-use crate::helpers_functions::{direction_value, is_good_tile, is_not_visualize};
-use crate::ENVIRONMENT;
+use crate::helpers_functions::direction_value;
+use crate::SENSE_COST;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use crate::weather_forecast::StepForecast;
+use crate::simulator::Simulator;
 
 
-//I set two bools for the InputDir, we use it for the thread paths:
-//The first if we need to destroy something.
-//The second if we need to put something. (I am lazy)
+// Minimum number of unknown tiles around us to consider a "Sense" useful.
+static SENSE_MIN_UNKNOWN:usize=10;
+
+
+// The four directions the robot can use.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputDir{
-    Right(bool,bool),
-    Left(bool,bool),
-    Top(bool,bool),
-    Bottom(bool,bool),
-    None
+    Right,
+    Left,
+    Top,
+    Bottom,
 }
 
 
@@ -29,91 +31,52 @@ impl InputDir{
     fn is_reverse(&self, other:&InputDir)->bool{
 
         match self{
-            InputDir::Top(_,_)=>{if std::mem::discriminant(other)==std::mem::discriminant(&InputDir::Bottom(false,false) ){true} else {false}},
-            InputDir::Left(_,_)=>{if std::mem::discriminant(other)==std::mem::discriminant(&InputDir::Right(false,false)) {true} else {false}},
-            InputDir::Bottom(_,_)=>{if std::mem::discriminant(other)==std::mem::discriminant(&InputDir::Top(false,false)) {true} else {false}},
-            InputDir::Right(_,_)=>{if std::mem::discriminant(other)==std::mem::discriminant(&InputDir::Left(false,false)) {true} else {false}}
-            _ => {false}
+            InputDir::Top=>*other==InputDir::Bottom,
+            InputDir::Left=>*other==InputDir::Right,
+            InputDir::Bottom=>*other==InputDir::Top,
+            InputDir::Right=>*other==InputDir::Left,
         }
 
     }
 
     pub(crate) fn property(&self) ->Direction{
         match self{
-            InputDir::Right(_,_) => Direction::Right,
-            InputDir::Left(_,_) => Direction::Left,
-            InputDir::Top(_,_) => Direction::Up,
-            InputDir::Bottom(_,_)=> Direction::Down,
-            _=>{Direction::Down}
+            InputDir::Right => Direction::Right,
+            InputDir::Left => Direction::Left,
+            InputDir::Top => Direction::Up,
+            InputDir::Bottom=> Direction::Down,
         }
     }
 
     fn random_input_dir()->InputDir{
-        let mut rng =thread_rng();
-        let t: i32 =rng.gen_range(0..5);
+        let mut rng =planner_rng();
+        let t: i32 =rng.gen_range(0..4);
 
         match t{
-            0=>InputDir::Bottom(false,false),
-            1=>InputDir::Left(false,false),
-            2=>InputDir::None,
-            3=>InputDir::Right(false,false),
-            _=>InputDir::Top(false,false),
+            0=>InputDir::Bottom,
+            1=>InputDir::Left,
+            2=>InputDir::Right,
+            _=>InputDir::Top,
         }
     }
 
     fn random_input_without_i(i:&InputDir)->InputDir{
-        let mut rng =thread_rng();
-        let t: i32 =rng.gen_range(0..4);
-
-        match i{
-            InputDir::Right(_, _) => {
-                match t{
-                    0=>InputDir::Bottom(false,false),
-                    1=>InputDir::Left(false,false),
-                    2=>InputDir::None,
-                    _=>InputDir::Top(false,false),
-                }
-            }
-            InputDir::Left(_, _) => {
-                match t{
-                    0=>InputDir::Bottom(false,false),
-                    1=>InputDir::None,
-                    2=>InputDir::Right(false,false),
-                    _=>InputDir::Top(false,false),
-                }
-            }
-            InputDir::Top(_, _) => {
-                match t{
-                    0=>InputDir::Bottom(false,false),
-                    1=>InputDir::Left(false,false),
-                    2=>InputDir::None,
-                    _=>InputDir::Right(false,false),
-                }
-            }
-            InputDir::Bottom(_, _) => {
-                match t{
-                    0=>InputDir::Left(false,false),
-                    1=>InputDir::None,
-                    2=>InputDir::Right(false,false),
-                    _=>InputDir::Top(false,false),
-                }
-            }
-            InputDir::None => {
-                match t{
-                    0=>InputDir::Bottom(false,false),
-                    1=>InputDir::Left(false,false),
-                    2=>InputDir::Right(false,false),
-                    _=>InputDir::Top(false,false),
-                }
-            }
+        let mut dir=InputDir::random_input_dir();
+        while dir==*i{
+            dir=InputDir::random_input_dir();
         }
+        dir
     }
 
-    pub fn convert_to_input_dir(mut x:i32,mut y:i32,vet:Vec<ChartedCoordinate>)->Vec<InputDir>{
+    //From the path of the charted tool we create the moves to follow
+    pub fn convert_to_actions(mut x:i32,mut y:i32,vet:Vec<ChartedCoordinate>)->Vec<Action>{
 
         let mut result=Vec::new();
         for i in vet{
-            result.push(InputDir::convert_int_to_input_dir(x, y, i.0 as i32, i.1 as i32));
+            match InputDir::convert_int_to_input_dir(x, y, i.0 as i32, i.1 as i32){
+                Some(dir) => result.push(Action::Move(dir)),
+                Option::None => {}
+            }
 
             x=i.0 as i32;
             y=i.1 as i32;
@@ -121,23 +84,76 @@ impl InputDir{
         result
     }
 
-    fn convert_int_to_input_dir(x:i32,y:i32,x1:i32,y1:i32)->InputDir{
+    fn convert_int_to_input_dir(x:i32,y:i32,x1:i32,y1:i32)->Option<InputDir>{
         match (x1-x,y1-y){
-            (0,1)=>InputDir::Right(false,false),
-            (0,-1)=>InputDir::Left(false,false),
-            (1,0)=>InputDir::Bottom(false,false),
-            (-1,0)=>InputDir::Top(false,false),
-            _=>{InputDir::None}
+            (0,1)=>Some(InputDir::Right),
+            (0,-1)=>Some(InputDir::Left),
+            (1,0)=>Some(InputDir::Bottom),
+            (-1,0)=>Some(InputDir::Top),
+            _=>{Option::None}
         }
     }
+}
+
 
-    fn convert_to_int(i:&InputDir)->(i32,i32){
-        match i{
-            InputDir::Right(_, _) => {(0,1)}
-            InputDir::Left(_, _) => {(0,-1)}
-            InputDir::Top(_, _) => {(-1,0)}
-            InputDir::Bottom(_, _) => {(1,0)}
-            InputDir::None => {(0,0)}
+// The genes of our robot paths. Every action is exactly what the robot will do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action{
+    Move(InputDir),
+    // Destroy the content of the tile in that direction (so we collect it)
+    Destroy(InputDir),
+    // Put the content, with that quantity, in the tile in that direction
+    Put(InputDir,Content,usize),
+    // We don't do anything for a tick
+    Wait,
+    // We look around with the sensing planner
+    Sense,
+    // From the teleport we are on, we go to the teleport in this position
+    Teleport((usize,usize)),
+}
+
+impl Action{
+
+    // Direction of the tile on which the action is done (or where we move).
+    pub(crate) fn direction(&self)->Option<InputDir>{
+        match self{
+            Action::Move(d) | Action::Destroy(d) | Action::Put(d,_,_) => Some(*d),
+            _ => Option::None,
+        }
+    }
+
+    // Offset of the tile on which the action is done.
+    pub(crate) fn target(&self)->(i32,i32){
+        match self.direction(){
+            Some(d) => direction_value(&d),
+            Option::None => (0,0),
+        }
+    }
+
+    pub(crate) fn is_move(&self)->bool{
+        match self{
+            Action::Move(_) => true,
+            _ => false,
+        }
+    }
+
+    // Random gene. Most of them are moves, since we want to arrive somewhere.
+    // "backpack" is what we have, so we can try to put it somewhere.
+    // "teleports" are the teleports we have activated, where we can jump.
+    fn random_action(backpack:&Vec<(Content,usize)>,teleports:&Vec<(usize,usize)>)->Action{
+        let mut rng =planner_rng();
+        let t: i32 =rng.gen_range(0..100);
+
+        match t{
+            0..=69=>Action::Move(InputDir::random_input_dir()),
+            70..=81=>Action::Destroy(InputDir::random_input_dir()),
+            82..=89=>Action::Wait,
+            90..=95 if !backpack.is_empty()=>{
+                let (content,quantity)=backpack[rng.gen_range(0..backpack.len())].clone();
+                Action::Put(InputDir::random_input_dir(),content,quantity)
+            }
+            96..=97 if !teleports.is_empty()=>Action::Teleport(teleports[rng.gen_range(0..teleports.len())]),
+            _=>Action::Sense,
         }
     }
 }
@@ -147,12 +163,16 @@ impl InputDir{
 // So, from it, we will create all our "robot path" used in the generations.
 #[derive(Clone,PartialEq)]
 pub struct GeneticSearch{
-    pub(crate) vector:Vec<InputDir>,
+    pub(crate) vector:Vec<Action>,
     pub(crate) cost:usize,
     pub(crate) distanze_from_dest:i32,
     start_x:i32,
     start_y:i32,
-    pub(crate) weight:i32
+    pub(crate) weight:i32,
+    // What the robot has in the backpack when we started the search
+    backpack:Arc<Vec<(Content,usize)>>,
+    // Teleports we know when we started the search
+    teleports:Arc<Vec<(usize,usize)>>,
 }
 
 impl Default for GeneticSearch{
@@ -165,13 +185,15 @@ impl Default for GeneticSearch{
             start_y:0,
             start_x:0,
             weight:INFINITE as i32,
+            backpack:Arc::new(Vec::new()),
+            teleports:Arc::new(Vec::new()),
         }
     }
 }
 
 impl GeneticSearch{
 
-    pub(crate) fn new(n:usize, x:i32, y:i32,inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>) ->Self{
+    pub(crate) fn new(n:usize, x:i32, y:i32,inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>,backpack:&Arc<Vec<(Content,usize)>>,teleports:&Arc<Vec<(usize,usize)>>) ->Self{
         let mut g=GeneticSearch{
             vector:Vec::new(),
             cost:INFINITE,
@@ -179,13 +201,15 @@ impl GeneticSearch{
             start_x:x,
             start_y:y,
             weight:1000,
+            backpack:Arc::clone(backpack),
+            teleports:Arc::clone(teleports),
         };
 
         g.generate_random_sequence(n,x,y,inside_thread_map);
         g
     }
 
-    fn new_with_vector(x:i32,y:i32,vector:Vec<InputDir>)->Self{
+    fn new_with_vector(x:i32,y:i32,vector:Vec<Action>,backpack:&Arc<Vec<(Content,usize)>>,teleports:&Arc<Vec<(usize,usize)>>)->Self{
         GeneticSearch{
             vector,
             cost:INFINITE,
@@ -193,201 +217,133 @@ impl GeneticSearch{
             start_y:y,
             start_x:x,
             weight:1000,
+            backpack:Arc::clone(backpack),
+            teleports:Arc::clone(teleports),
         }
     }
 
-    fn generate_random_sequence(&mut self,n:usize,mut x:i32, mut y:i32,inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>){
-
-        let mut x_dem=x;
-        let mut y_dem=y;
+    fn generate_random_sequence(&mut self,n:usize,x:i32,y:i32,inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>){
+        let simulator=Simulator::new(inside_thread_map);
+        let mut state=simulator.start((x as usize,y as usize),0);
 
         for _ in 0..n{
-            let mut dir=InputDir::random_input_dir();
-
-            (x_dem,y_dem)=InputDir::convert_to_int(&dir);
-
-            let mut x_s=x+x_dem;
-            let mut y_s=y+y_dem;
+            let mut action=Action::random_action(&self.backpack,&self.teleports);
 
-            if x_s<0{
-                x_s=0;
+            //Only the moves change our position
+            if action.is_move(){
+                action=repair_move(&simulator,state.position,&action);
             }
-            if y_s<0 {
-                y_s=0;
-            }
-
-            if is_not_visualize(x_s,y_s) || inside_thread_map[x_s as usize][y_s as usize].is_none(){self.vector.push(InputDir::None);continue;}
-
-
-            while !is_good_tile(&inside_thread_map[x_s as usize][y_s as usize]){
-                x_s=x;
-                y_s=y;
-
-                dir=InputDir::random_input_dir();
-
-                (x_dem,y_dem)=InputDir::convert_to_int(&dir);
 
-                x_s+=x_dem;
-                y_s+=y_dem;
-
-                if is_not_visualize(x,y){dir=InputDir::None;break;}
+            //If we are on a teleport, half of the times we try to use it.
+            if simulator.on_teleport(state.position) && !self.teleports.is_empty() && planner_rng().gen_range(0..2)==0{
+                action=Action::Teleport(self.teleports[planner_rng().gen_range(0..self.teleports.len())]);
             }
 
-            x=x_s;
-            y=y_s;
+            simulator.step(&mut state,&action);
 
             //The rng is used because it is faster to catch the generated value if we have to launch it a lot of times.
-            self.vector.push(dir);
-
+            self.vector.push(action);
         }
 
     }
 
 
-    pub(crate) fn genetic_cost(&mut self, inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>, destination:(usize, usize)){
-        let mut x=self.start_x;
-        let mut y=self.start_y;
-
-        let mut next_x=x;
-        let mut next_y=y;
-
+    //The forecast is used to know the weather when the robot will do every step, since it needs to recharge while walking.
+    // "environment" are the conditions of when the search started, used if we don't have a forecast.
+    pub(crate) fn genetic_cost(&mut self, inside_thread_map:&Arc<Vec<Vec<Option<Tile>>>>, destination:(usize, usize), environment:&Option<EnvironmentalConditions>, forecast:&Arc<StepForecast>){
         let mut backtracking=0;
 
         let mut null_block=0;
 
         let mut object_destroy=0;
 
-        let mut sha_water=0;
-
-        let mut set:HashSet<(i32,i32)>=HashSet::new();
-
-        self.cost=0;
-
-        for ele in self.vector.iter_mut(){
+        let mut deposit=0;
 
-            let (i,j)=direction_value(ele);
+        let mut sensed=0;
 
-            next_x=x+i;
-            next_y=y+j;
+        let mut sha_water=0;
 
+        let mut teleported=0;
 
-            if is_not_visualize(next_x, next_y) || inside_thread_map[next_x as usize][next_y as usize].is_none(){
-                null_block+=1;
-                *ele=InputDir::None;
-                continue;
-            }
+        // Fish, bushes and rocks have their own reward, they are less important than what we deposit.
+        let mut fished=0;
 
-            if !is_good_tile(&inside_thread_map[next_x as usize][next_y as usize]){
-                null_block+=1;
+        let mut harvested=0;
 
-                let mut e =ele.clone();
+        let mut rocks=0;
 
+        let mut extinguished=0;
 
-                let mut save_x=x+direction_value(&e).0;
+        let simulator=Simulator::new(inside_thread_map).with_environment(environment.as_ref()).with_forecast(forecast).with_backpack(&self.backpack).with_sense_cost(*SENSE_COST.lock().unwrap());
+        let mut state=simulator.start((self.start_x as usize,self.start_y as usize),0);
 
-                if save_x<0{
-                    save_x=0
-                }
-
-                let mut save_y=y+direction_value(&e).1;
+        for ele in self.vector.iter_mut(){
 
-                if save_y<0{
-                    save_y=0;
+            //A move into a tile we can't walk is fixed, but we penalize it.
+            if ele.is_move(){
+                let repaired=repair_move(&simulator,state.position,ele);
+                if repaired!=*ele{
+                    null_block+=1;
+                    *ele=repaired;
                 }
+            }
 
+            let position=state.position;
+            let step=simulator.step(&mut state,ele);
 
-                while e==*ele ||
-                    is_not_visualize(save_x, save_y) ||
-                    inside_thread_map[save_x as usize][save_y as usize].is_none() ||
-                    !is_good_tile(&inside_thread_map[save_x as usize][save_y as usize]) {
-
-                    e=InputDir::random_input_without_i(&e);
-
-                    save_x=x+direction_value(&e).0;
-
-                    if save_x<0{
-                        save_x=0;
+            match ele{
+                Action::Wait => {}
+                Action::Sense => {
+                    if unknown_around(inside_thread_map,position.0 as i32,position.1 as i32,DISTANCE as i32)>=SENSE_MIN_UNKNOWN{
+                        sensed+=1;
+                    }else{
+                        null_block+=1;
                     }
-
-                    save_y=y+direction_value(&e).1;
-
-                    if save_y<0{
-                        save_y=0;
+                }
+                Action::Destroy(_) => {
+                    // We only reward the contents we search.
+                    //The simulator doesn't collect two times the same block,
+                    //this way he can't exploit the fact to constantly go in the same block to lower his weight.
+                    match &step.collected{
+                        Some(Content::Fish(_)) => fished+=1,
+                        Some(Content::Bush(_)) => harvested+=1,
+                        Some(Content::Rock(_)) => rocks+=1,
+                        Some(_) => object_destroy+=1,
+                        Option::None => null_block+=1,
                     }
-
                 }
-
-                *ele=e;
-
-                let (i,j)=direction_value(ele);
-                next_x=x+i;
-                next_y=y+j;
-            }
-
-
-            if next_x<0{
-                next_x=0;
-            }
-            if next_y<0{
-                next_y=0;
-            }
-
-            if *ele!=InputDir::None{
-                self.cost=self.cost+genetic_cost(
-                    (x as usize,y as usize),
-                    (next_x as usize,next_y as usize),
-                    inside_thread_map);
-            }
-
-
-            if inside_thread_map[next_x as usize][next_y as usize].is_some(){
-                let tile=inside_thread_map[next_x as usize][next_y as usize].as_ref().unwrap();
-
-                //Because the shallow water doesn't cost much and he keeps walking in there.
-                if tile.tile_type==ShallowWater{
-                    sha_water+=1;
+                Action::Put(_,_,_) => {
+                    //We only want to put the content in its container (or the water on the fire)
+                    if step.extinguished{
+                        extinguished+=1;
+                    }else if step.deposited.is_some(){
+                        deposit+=1;
+                    }else{
+                        null_block+=1;
+                    }
                 }
-
-
-                // We check if the next tile contains the contents we search.
-                if !set.contains(&(next_x, next_y)) && (tile.content.to_default()==Coin(0) || tile.content.to_default()==Garbage(0) || tile.content.to_default()==Tree(0)){
-
-                    object_destroy+=1;
-
-                    //We don't want backtracking to the same block.
-                    //This way he can't exploit the fact to constantly go in the same block to lower his weight.
-                    set.insert((next_x,next_y));
-
-                    self.cost+=tile.content.properties().cost();
-
-                    //We can consider to grab the object since we walk past it.
-                    let element=ele.clone();
-
-                    *ele=match element{
-                        InputDir::Right(_,_) => InputDir::Right(true,false),
-                        InputDir::Left(_,_) => InputDir::Left(true,false),
-                        InputDir::Top(_,_) => InputDir::Top(true,false),
-                        InputDir::Bottom(_,_) => InputDir::Bottom(true,false),
-                        InputDir::None => InputDir::None,
+                Action::Teleport(_) => {
+                    //We reward the teleport only if it brings us closer to the destination.
+                    let before=distance(position,destination);
+                    if !step.blocked && distance(step.position,destination)<before{
+                        teleported+=1;
+                    }else{
+                        null_block+=1;
                     }
-                }else{
-                    let element=ele.clone();
-                    *ele=match element{
-                        InputDir::Right(_,_) => InputDir::Right(false,false),
-                        InputDir::Left(_,_) => InputDir::Left(false,false),
-                        InputDir::Top(_,_) => InputDir::Top(false,false),
-                        InputDir::Bottom(_,_) => InputDir::Bottom(false,false),
-                        InputDir::None => InputDir::None,
+                }
+                Action::Move(_) => {
+                    //Because the shallow water doesn't cost much and he keeps walking in there.
+                    if !step.blocked && inside_thread_map[step.position.0][step.position.1].as_ref().unwrap().tile_type==ShallowWater{
+                        sha_water+=1;
                     }
                 }
             }
+        }
 
-            if is_good_tile(&inside_thread_map[next_x as usize][next_y as usize]){
-                x=next_x;
-                y=next_y;
-            }
+        self.cost=state.spent;
 
-        }
+        let x=state.position.0 as i32;
+        let y=state.position.1 as i32;
         self.distanze_from_dest=(x-destination.0 as i32).abs()+(y-destination.1 as i32).abs();
 
 
@@ -398,9 +354,10 @@ impl GeneticSearch{
         // BackTracking=2
         //I want to penalize the fact that he waste those 4 actions
 
-        let mut vet=Vec::new();
+        let mut vet:Vec<InputDir>=Vec::new();
         for i in self.vector.iter(){
-            if *i==InputDir::None{continue;}
+            if !i.is_move(){continue;}
+            let i=i.direction().unwrap();
             if vet.is_empty(){
                 vet.push(i);
             }
@@ -416,14 +373,118 @@ impl GeneticSearch{
 
         //We fixed the weight based on the cost(10%)+backtracking(25%)+null_block(25%)-object_destroyed(25%)+shallow water(15%)
         //I also put a weight on the shallow water since it doesn't cost much and he keep walking in there.
+        //The deposits and the useful sensing are rewarded like the objects destroyed.
+        //A teleport that takes us closer is rewarded more, since it saves a lot of moves.
+        //An extinguished fire opens the way, fish and rocks are worth something, bushes a bit less.
 
-        self.weight=((self.cost as f32*0.01)+((backtracking*10)as f32*0.15)+((null_block*10)as f32*0.25)-((object_destroy*10)as f32*0.25)+((sha_water*50)as f32*0.34)) as i32;
+        let others=fished*8+rocks*5+harvested*3+extinguished*15;
+        self.weight=((self.cost as f32*0.01)+((backtracking*10)as f32*0.15)+((null_block*10)as f32*0.25)-(((object_destroy+deposit+sensed)*10)as f32*0.25)-((teleported*30)as f32*0.25)-(others as f32*0.25)+((sha_water*50)as f32*0.34)) as i32;
         //println!("Specific weight:{}",self.weight);
     }
 
 }
 
 
+// If the move can't be done we try another direction.
+// If the tile is unknown (or we are surrounded) we wait instead.
+fn repair_move(simulator:&Simulator,position:(usize,usize),action:&Action)->Action{
+    let x=position.0 as i32;
+    let y=position.1 as i32;
+
+    let mut e=match action.direction(){
+        Some(d) => d,
+        Option::None => return action.clone(),
+    };
+
+    let (i,j)=direction_value(&e);
+    if !simulator.is_known(x+i,y+j){return Action::Wait}
+    if simulator.can_enter(x+i,y+j){return action.clone()}
+
+    for _ in 0..20{
+        e=InputDir::random_input_without_i(&e);
+
+        let (i,j)=direction_value(&e);
+        if simulator.can_enter(x+i,y+j){return Action::Move(e)}
+    }
+
+    Action::Wait
+}
+
+
+fn distance(a:(usize,usize),b:(usize,usize))->usize{
+    ((a.0 as i32-b.0 as i32).abs()+(a.1 as i32-b.1 as i32).abs()) as usize
+}
+
+
+// How many tiles we don't know around (x,y)
+fn unknown_around(map:&Arc<Vec<Vec<Option<Tile>>>>,x:i32,y:i32,distance:i32)->usize{
+    let mut unknown=0;
+    for i in x-distance..=x+distance{
+        for j in y-distance..=y+distance{
+            if i<0 || j<0 || i as usize>=map.len() || j as usize>=map.len(){continue}
+            if map[i as usize][j as usize].is_none(){
+                unknown+=1;
+            }
+        }
+    }
+    unknown
+}
+
+
+// The search of a single thread: a population that evolves towards "destination", starting from "start".
+// We return the best element of the last generation.
+pub(crate) fn evolve(map:&Arc<Vec<Vec<Option<Tile>>>>,start:(usize,usize),destination:(usize,usize),environment:&Option<EnvironmentalConditions>,forecast:&Arc<StepForecast>,backpack:&Arc<Vec<(Content,usize)>>,teleports:&Arc<Vec<(usize,usize)>>)->GeneticSearch{
+    let (x,y)=start;
+    let mut genetic_set=Vec::new();
+
+    //Initial population
+    for _ in 0..POPULATION_NUMBER{
+        genetic_set.push(GeneticSearch::new(INPUT_DIR_SIZE,x as i32,y as i32,map,backpack,teleports));
+    }
+
+    //We repeat the Selection, Crossover and mutation:
+    for _ in 0..GENERATION_LIMIT{
+        //I fixed the generation limit to 150, which is optimal, since also the children learn from the parents.
+
+        //Genetic Fitness, we calculate the weight of the random generated directions
+        for i in genetic_set.iter_mut(){
+            i.genetic_cost(map,destination,environment,forecast);
+        }
+
+        //Genetic Selection: we take an elite set and one based on probability.
+        //This way, also the children can learn.
+        //We take the first and second based on the distance from destination and the weight.
+        let (first,second)=genetic_selection(&mut genetic_set);
+
+        //Genetic crossover. Here we generate new sons from first and second (two strongest set)
+        genetic_crossover(&mut genetic_set,&first,&second,&x,&y);
+
+        //Genetic mutation. Where are going to change a some value for escaping the local min problem.
+        genetic_mutation(&mut genetic_set);
+
+        //We also push the two winning parents with their children
+        // The two strongest set won't have to mutate. If they need, they will do that the next cycle
+        genetic_set.push(first);
+        genetic_set.push(second);
+    }
+
+    //We generate the last generation:
+    for i in genetic_set.iter_mut(){
+        i.genetic_cost(map,destination,environment,forecast);
+    }
+
+    //we take the fastest sample:
+    let mut index_res=0;
+    for i in genetic_set.iter().enumerate(){
+        if i.1.distanze_from_dest<=genetic_set[index_res].distanze_from_dest{
+            if i.1.weight<genetic_set[index_res].weight{
+                index_res=i.0;
+            }
+        }
+    }
+    genetic_set.swap_remove(index_res)
+}
+
 pub fn genetic_selection(population:&mut Vec<GeneticSearch>)->(GeneticSearch,GeneticSearch){
     population.sort_by(|a,b| {
         let d=a.distanze_from_dest.cmp(&b.distanze_from_dest);
@@ -435,7 +496,7 @@ pub fn genetic_selection(population:&mut Vec<GeneticSearch>)->(GeneticSearch,Gen
     });
 
     let probability_choice=||->usize{
-        let t=thread_rng().gen_range(0..28);
+        let t=planner_rng().gen_range(0..28);
         match t{
             0..=6=>1,
             7..=12=>2,
@@ -459,14 +520,16 @@ pub fn genetic_selection(population:&mut Vec<GeneticSearch>)->(GeneticSearch,Gen
 }
 
 pub fn genetic_mutation(population:&mut Vec<GeneticSearch>){
-    let mut rng =thread_rng();
+    let mut rng =planner_rng();
     for element in population.iter_mut(){
+        let backpack=Arc::clone(&element.backpack);
+        let teleports=Arc::clone(&element.teleports);
         for i in element.vector.iter_mut(){
             let probability=rng.gen_range(0..10);
             //That's equivalent to 10% of probability
             if probability<=0{
                 let mut g=i.clone();
-                while *i==g{ g=InputDir::random_input_dir(); }
+                while *i==g{ g=Action::random_action(&backpack,&teleports); }
 
                 *i=g;
 
@@ -485,39 +548,10 @@ pub fn genetic_crossover(population:&mut Vec<GeneticSearch>,first:&GeneticSearch
     let second_part=[&first.vector[piece..2*piece],&second.vector[piece..2*piece]];
     let third_part=[&first.vector[2*piece..],&second.vector[2*piece..]];
 
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[1],third_part[0]].concat()));
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[1],third_part[1]].concat()));
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[0],third_part[0]].concat()));
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[0],third_part[1]].concat()));
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[0],third_part[1]].concat()));
-    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[1],third_part[0]].concat()));
-}
-
-
-pub fn genetic_cost(current_coord: (usize, usize), target_coord: (usize, usize), map:&Arc<Vec<Vec<Option<Tile>>>>) -> usize {
-    // Get tiles
-    let target_tile = map[target_coord.0][target_coord.1].clone().unwrap();
-    let current_tile = map[current_coord.0][current_coord.1].clone().unwrap();
-
-    // Init costs
-    let mut base_cost = target_tile.tile_type.properties().cost();
-    let mut elevation_cost = 0;
-
-
-    // Get information's that influence the cost
-    let environmental_conditions = ENVIRONMENT.lock().unwrap().to_owned().unwrap();
-
-
-    let new_elevation = target_tile.elevation;
-    let current_elevation = current_tile.elevation;
-
-    // Calculate cost
-    base_cost = calculate_cost_go_with_environment(base_cost, environmental_conditions, target_tile.tile_type);
-
-    // Consider elevation cost only if we are going from a lower tile to a higher tile
-    if new_elevation > current_elevation {
-        elevation_cost = (new_elevation - current_elevation).pow(2);
-    }
-
-    base_cost + elevation_cost
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[1],third_part[0]].concat(),&first.backpack,&first.teleports));
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[1],third_part[1]].concat(),&first.backpack,&first.teleports));
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[0],third_part[0]].concat(),&first.backpack,&first.teleports));
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[0],third_part[1]].concat(),&first.backpack,&first.teleports));
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[0],second_part[0],third_part[1]].concat(),&first.backpack,&first.teleports));
+    population.push(GeneticSearch::new_with_vector(*x as i32,*y as i32,[first_part[1],second_part[1],third_part[0]].concat(),&first.backpack,&first.teleports));
 }
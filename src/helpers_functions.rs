@@ -1,22 +1,18 @@
 
-use std::sync::Arc;
-use crate::{ALREADY_VISITED, CONTENT, InputDir, MovesToFollow};
+use crate::{ALREADY_VISITED, CONFIG, InputDir};
 use crate::ONE_DIRECTION_DISTANCE;
 use crate::DISTANCE;
 use crate::PositionToGo;
-use crate::WORLD_SIZE;
-use robotics_lib::world::tile::{Tile, TileType};
+use robotics_lib::world::tile::{Content, Tile, TileType};
 use robotics_lib::world::tile::TileType::{DeepWater, Lava};
-use crate::genetic_algorithm::genetic_cost;
 
 
 pub(crate) fn direction_value(value:&InputDir)->(i32,i32){
     match value{
-        InputDir::None=>(0,0),
-        InputDir::Top(_,_)=>(-1,0),
-        InputDir::Right(_,_)=>(0,1),
-        InputDir::Left(_,_)=>(0,-1),
-        InputDir::Bottom(_,_)=>(1,0),
+        InputDir::Top=>(-1,0),
+        InputDir::Right=>(0,1),
+        InputDir::Left=>(0,-1),
+        InputDir::Bottom=>(1,0),
     }
 }
 
@@ -46,8 +42,9 @@ pub(crate) fn is_good_tile(data:&Option<Tile>) ->bool{
     }
 }
 
-pub(crate) fn is_not_visualize(next_x:i32, next_y:i32) ->bool{
-    if next_y>WORLD_SIZE as i32|| next_x>WORLD_SIZE as i32 || next_x<0 || next_y<0 {
+// The position is outside a map of this size (the world we generated can be smaller than WORLD_SIZE).
+pub(crate) fn is_not_visualize(next_x:i32, next_y:i32, size:usize) ->bool{
+    if next_y>=size as i32|| next_x>=size as i32 || next_x<0 || next_y<0 {
         true
     }else{
         false
@@ -63,54 +60,22 @@ pub (crate) fn already_visited(x:i32,y:i32)->bool{
 }
 
 
-pub (crate) fn calculate_cost_dir(moves:&MovesToFollow,mut x:usize,mut y:usize,map:&Vec<Vec<Option<Tile>>>)->usize{
-
-    let mut next_x=x as i32;
-    let mut next_y=y as i32;
-
-    let mut cost:usize=0;
-
-
-    for ele in moves.path_to_follow.iter(){
-        let (i,j)=direction_value(ele);
-
-        next_x=x as i32+i;
-        next_y=y as i32+j;
-
-        if *ele==InputDir::None || map[next_x as usize][next_y as usize].is_none(){continue;}
+// Container where we have to put the content (it depends on the policy of the configuration).
+pub(crate) fn respective_container(content:&Content)->Content{
+    CONFIG.policy.container_for(content)
+}
 
 
-        match ele{
-            InputDir::Right(_, true) => {
-                let c=CONTENT.lock().unwrap().content.clone();
-                let quantity=CONTENT.lock().unwrap().quantity;
-                cost=cost+(c.properties().cost()*quantity);
-            }
-            InputDir::Left(_, true) => {
-                let c=CONTENT.lock().unwrap().content.clone();
-                let quantity=CONTENT.lock().unwrap().quantity;
-                cost=cost+(c.properties().cost()*quantity);
-            }
-            InputDir::Top(_, true) => {
-                let c=CONTENT.lock().unwrap().content.clone();
-                let quantity=CONTENT.lock().unwrap().quantity;
-                cost=cost+(c.properties().cost()*quantity);
-            }
-            InputDir::Bottom(_, true) => {
-                let c=CONTENT.lock().unwrap().content.clone();
-                let quantity=CONTENT.lock().unwrap().quantity;
-                cost=cost+(c.properties().cost()*quantity);
-            }
-            _ => {
-                cost=cost+genetic_cost((x,y),(next_x as usize,next_y as usize),&Arc::new(map.clone()));
+// Positions of the teleports we have activated.
+pub(crate) fn known_teleports(map:&Vec<Vec<Option<Tile>>>)->Vec<(usize,usize)>{
+    let mut result=Vec::new();
+    for (i,row) in map.iter().enumerate(){
+        for (j,tile) in row.iter().enumerate(){
+            match tile{
+                Some(t) if t.tile_type==TileType::Teleport(true) => result.push((i,j)),
+                _ => {}
             }
         }
-
-
-        x=next_x as usize;
-        y=next_y as usize;
-
     }
-
-    cost
+    result
 }
@@ -0,0 +1,241 @@
+use std::fs;
+use robotics_lib::world::tile::{Content, Tile};
+use crate::world_file::{number, parse_content_value, parse_tile, tile_to_string};
+
+
+// If the format changes, we change the version: we don't load the files of the other versions.
+static HEADER:&str="trust_us_knowledge 1";
+
+// File we write at the end of the run (and every SAVE_EVERY ticks), "--knowledge <file>" to change it.
+pub static KNOWLEDGE_FILE:&str="knowledge.txt";
+pub static SAVE_EVERY:usize=500;
+
+
+// What the robot knows about the world, so a new run can continue from here ("--resume <file>").
+// The file is text, divided in sections:
+//
+//      trust_us_knowledge 1
+//      world ghost seed=42 size=500 variation=0.1     (see WorldSource::fingerprint, "random" if we can't know)
+//      size 500
+//      tiles                   (a row for every row of the map, "?" for the tiles we don't know)
+//      ? Grass:0 Sand:1:Rock(2) ...
+//      visited                 (a row of 0 and 1 for every row)
+//      0010...
+//      containers              (position, kind, capacity, failed puts)
+//      10 12 Bin(0..0) 5 0
+//      markets                 (position, trades left)
+//      40 3 2
+//      roads                   (position, how many times we walked it)
+//      10 13 4
+//      paved
+//      10 14
+#[derive(Debug,Clone,Default)]
+pub struct Knowledge{
+    // The world where we learned it
+    pub(crate) world:String,
+    pub(crate) tiles:Vec<Vec<Option<Tile>>>,
+    pub(crate) visited:Vec<Vec<bool>>,
+    pub(crate) containers:Vec<((usize,usize),Content,usize,usize)>,
+    pub(crate) markets:Vec<((usize,usize),usize)>,
+    pub(crate) roads:Vec<((usize,usize),usize)>,
+    pub(crate) paved:Vec<(usize,usize)>,
+}
+
+impl Knowledge{
+
+    // The file given with "--resume", if there is one and it was written in the same world.
+    pub(crate) fn from_args(world:Option<String>)->Option<Knowledge>{
+        let args:Vec<String>=std::env::args().collect();
+        let filename=match args.iter().position(|a| a=="--resume"){
+            Some(i) if i+1<args.len() => args[i+1].clone(),
+            _ => return Option::None,
+        };
+
+        let world=match world{
+            Some(w) => w,
+            Option::None => {
+                println!("We don't use the knowledge in {}: the world is random, so it isn't the same world",filename);
+                return Option::None;
+            }
+        };
+
+        match Knowledge::load(&filename){
+            Ok(k) if k.world!=world => {
+                println!("We don't use the knowledge in {}: it is of the world \"{}\", we are in \"{}\"",filename,k.world,world);
+                Option::None
+            }
+            Ok(k) => {
+                println!("Knowledge loaded from {}: {} known tiles, {} containers, {} markets",filename,k.known_tiles(),k.containers.len(),k.markets.len());
+                Some(k)
+            }
+            Err(e) => {
+                println!("Error loading the knowledge from {}:{}",filename,e);
+                Option::None
+            }
+        }
+    }
+
+    pub(crate) fn filename()->String{
+        let args:Vec<String>=std::env::args().collect();
+        match args.iter().position(|a| a=="--knowledge"){
+            Some(i) if i+1<args.len() => args[i+1].clone(),
+            _ => KNOWLEDGE_FILE.to_string(),
+        }
+    }
+
+    pub(crate) fn known_tiles(&self)->usize{
+        self.tiles.iter().map(|row| row.iter().filter(|t| t.is_some()).count()).sum()
+    }
+
+    pub(crate) fn save(&self,filename:&str)->Result<(),String>{
+        fs::write(filename,self.to_text()).map_err(|e| format!("{}: {}",filename,e))
+    }
+
+    pub(crate) fn to_text(&self)->String{
+        let mut s=format!("{}\nworld {}\nsize {}\ntiles\n",HEADER,self.world,self.tiles.len());
+
+        for row in self.tiles.iter(){
+            let line:Vec<String>=row.iter().map(|t| match t{
+                Some(tile) => tile_to_string(tile),
+                Option::None => String::from("?"),
+            }).collect();
+            s.push_str(&line.join(" "));
+            s.push('\n');
+        }
+
+        s.push_str("visited\n");
+        for row in self.visited.iter(){
+            s.extend(row.iter().map(|v| if *v{'1'}else{'0'}));
+            s.push('\n');
+        }
+
+        s.push_str("containers\n");
+        for ((x,y),kind,capacity,failed) in self.containers.iter(){
+            s.push_str(&format!("{} {} {:?} {} {}\n",x,y,kind,capacity,failed));
+        }
+
+        s.push_str("markets\n");
+        for ((x,y),trades) in self.markets.iter(){
+            s.push_str(&format!("{} {} {}\n",x,y,trades));
+        }
+
+        s.push_str("roads\n");
+        for ((x,y),count) in self.roads.iter(){
+            s.push_str(&format!("{} {} {}\n",x,y,count));
+        }
+
+        s.push_str("paved\n");
+        for (x,y) in self.paved.iter(){
+            s.push_str(&format!("{} {}\n",x,y));
+        }
+        s
+    }
+
+    pub(crate) fn load(filename:&str)->Result<Knowledge,String>{
+        let text=fs::read_to_string(filename).map_err(|e| format!("{}: {}",filename,e))?;
+        Knowledge::parse(&text)
+    }
+
+    pub(crate) fn parse(text:&str)->Result<Knowledge,String>{
+        let mut lines=text.lines();
+        if lines.next().map(|l| l.trim())!=Some(HEADER){
+            return Err(String::from("this is not a knowledge file (or the version is different)"));
+        }
+
+        let mut k=Knowledge::default();
+        let mut section="";
+
+        for (n,line) in lines.enumerate(){
+            let line=line.trim();
+            if line.is_empty(){continue}
+
+            let mut parts=line.split_whitespace();
+            let error=|e:String| format!("line {}: {}",n+2,e);
+
+            match line{
+                "tiles" | "visited" | "containers" | "markets" | "roads" | "paved" => {
+                    section=line;
+                    continue;
+                }
+                _ => {}
+            }
+
+            match section{
+                "" if line.starts_with("size") => {}
+                "" if line.starts_with("world") => k.world=line["world".len()..].trim().to_string(),
+                "tiles" => {
+                    let row=parts.map(|t| if t=="?"{Ok(Option::None)}else{parse_tile(t).map(Some)})
+                        .collect::<Result<Vec<Option<Tile>>,String>>().map_err(error)?;
+                    k.tiles.push(row);
+                }
+                "visited" => k.visited.push(line.chars().map(|c| c=='1').collect()),
+                "containers" => {
+                    let position=(number(parts.next()).map_err(error)?,number(parts.next()).map_err(error)?);
+                    let kind=parse_content_value(parts.next().unwrap_or("")).map_err(error)?;
+                    k.containers.push((position,kind,number(parts.next()).map_err(error)?,number(parts.next()).map_err(error)?));
+                }
+                "markets" => k.markets.push(((number(parts.next()).map_err(error)?,number(parts.next()).map_err(error)?),number(parts.next()).map_err(error)?)),
+                "roads" => k.roads.push(((number(parts.next()).map_err(error)?,number(parts.next()).map_err(error)?),number(parts.next()).map_err(error)?)),
+                "paved" => k.paved.push((number(parts.next()).map_err(error)?,number(parts.next()).map_err(error)?)),
+                _ => return Err(error(format!("unexpected \"{}\"",line))),
+            }
+        }
+
+        if k.visited.len()!=k.tiles.len(){
+            return Err(String::from("the visited tiles and the map have different sizes"));
+        }
+        Ok(k)
+    }
+}
+
+
+#[cfg(test)]
+mod tests{
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+    use super::Knowledge;
+
+    // What the robot knows of a 3x3 world, after a few ticks.
+    fn knowledge()->Knowledge{
+        let grass=Some(Tile{tile_type:TileType::Grass,content:Content::None,elevation:1});
+        let bin=Some(Tile{tile_type:TileType::Sand,content:Content::Bin(0..5),elevation:0});
+
+        Knowledge{
+            world:String::from("ascii worlds/small.txt"),
+            tiles:vec![
+                vec![grass.clone(),grass.clone(),Option::None],
+                vec![grass.clone(),bin,Option::None],
+                vec![Option::None,Option::None,Option::None],
+            ],
+            visited:vec![vec![true,false,false],vec![true,true,false],vec![false,false,false]],
+            containers:vec![((1,1),Content::Bin(0..5),5,1)],
+            markets:vec![((0,2),3)],
+            roads:vec![((0,1),4)],
+            paved:vec![(0,0)],
+        }
+    }
+
+    #[test]
+    fn saved_knowledge_is_parsed_back_the_same(){
+        let k=knowledge();
+        let parsed=Knowledge::parse(&k.to_text()).unwrap();
+
+        assert_eq!(parsed.world,k.world);
+        assert_eq!(parsed.known_tiles(),4);
+        assert_eq!(parsed.tiles.len(),3);
+        assert_eq!(parsed.tiles[1][1].as_ref().unwrap().content,Content::Bin(0..5));
+        assert_eq!(parsed.tiles[0][0].as_ref().unwrap().elevation,1);
+        assert!(parsed.tiles[2].iter().all(|t| t.is_none()));
+        assert_eq!(parsed.visited,k.visited);
+        assert_eq!(parsed.containers,k.containers);
+        assert_eq!(parsed.markets,k.markets);
+        assert_eq!(parsed.roads,k.roads);
+        assert_eq!(parsed.paved,k.paved);
+    }
+
+    #[test]
+    fn visited_tiles_must_have_the_size_of_the_map(){
+        let mut k=knowledge();
+        k.visited.push(vec![false;3]);
+        assert!(Knowledge::parse(&k.to_text()).is_err());
+    }
+}
@@ -1,20 +1,44 @@
 mod export_of_image;
 mod genetic_algorithm;
 mod helpers_functions;
-
-
-use std::collections::HashMap;
+mod sensing_planner;
+mod energy_model;
+mod path_segments;
+mod stuck_detector;
+mod event_log;
+mod weather_forecast;
+mod simulator;
+mod tour_planner;
+mod coverage;
+mod config;
+mod container_registry;
+mod market;
+mod content_handling;
+mod road_planner;
+mod content_search;
+mod audio_feedback;
+mod terminal_view;
+mod world_source;
+mod world_file;
+mod ascii_world;
+mod knowledge;
+mod run_log;
+mod planner_rng;
+mod benchmark;
+
+
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::sync::{Arc, Mutex};
 use std::thread::{sleep, spawn};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use robotics_lib::energy::Energy;
 use robotics_lib::event::events::Event;
-use robotics_lib::interface::{debug, destroy, Direction, go, look_at_sky, one_direction_view, put, robot_map};
+use robotics_lib::interface::{debug, destroy, go, look_at_sky, one_direction_view, put, robot_map, teleport};
 use robotics_lib::runner::{Robot, Runnable, Runner};
 use robotics_lib::runner::backpack::BackPack;
 use robotics_lib::world::coordinates::Coordinate;
-use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::tile::{Content, Tile, TileType};
 use robotics_lib::world::tile::Content::*;
 use robotics_lib::world::World;
 use robotics_lib::utils::LibError;
@@ -22,17 +46,39 @@ use robotics_lib::utils::LibError::{NotEnoughEnergy, OperationNotAllowed};
 use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
 
 use rust_eze_spotlight::Spotlight;
-use ghost_amazeing_island::world_generator::*;
+use asfalt_inator::{Asphaltinator, Shape};
+use sense_and_find_by_rustafariani::Lssf;
 use charting_tools::ChartingTools;
 use charting_tools::charted_coordinate::ChartedCoordinate;
 use charting_tools::charted_paths::ChartedPaths;
 
-use genetic_algorithm::{InputDir,GeneticSearch,genetic_selection,genetic_mutation,genetic_crossover};
-use helpers_functions::{get_next_position,is_not_visualize,is_good_tile,direction_value};
+use genetic_algorithm::{Action,InputDir,GeneticSearch,evolve};
+use helpers_functions::{get_next_position,is_not_visualize,is_good_tile,known_teleports,direction_value};
 
 use lazy_static::lazy_static;
 use robotics_lib::world::tile::TileType::ShallowWater;
-use crate::helpers_functions::{already_visited, calculate_cost_dir};
+use crate::helpers_functions::already_visited;
+use crate::sensing_planner::{SensingPlanner, SensorCall, SENSING_RESERVE};
+use crate::energy_model::{ActionKind, EnergyModel, VIEW_COST_PER_TILE};
+use crate::path_segments::{split_in_segments, PathSegment, MAX_ENERGY, SEGMENT_RESERVE};
+use crate::stuck_detector::{known_tiles, StuckDetector};
+use crate::event_log::{EventLog, EVENT_LOG_FILE};
+use crate::weather_forecast::{best_wait, Forecast, StepForecast};
+use crate::tour_planner::{tour_stops, StopKind, TourProblem, TOUR_MIN_STOPS, TOUR_PLANNING};
+use crate::planner_rng::seed_planner;
+use crate::coverage::{CoveragePlanner, Goal, COVERAGE_FILE};
+use crate::config::Config;
+use crate::container_registry::ContainerRegistry;
+use crate::market::Markets;
+use crate::content_handling::{content_use, quantity_in, ContentUse};
+use crate::road_planner::{Corridor, RoadPlanner, BUILD_ROADS};
+use crate::content_search::{search_cost, ContentSearch, SEARCH_GRANULARITY};
+use crate::audio_feedback::{AudioFeedback, Cue, AUDIO_FILE};
+use crate::terminal_view::{TerminalView, ViewState};
+use crate::world_file::{WorldFile, SNAPSHOT_FILE, SNAPSHOT_REQUEST};
+use crate::knowledge::{Knowledge, SAVE_EVERY};
+use crate::run_log::{action_to_string, call_to_string, Call, RunLog, RunMode};
+use crate::benchmark::{is_batch, run_batch, summary_file, tick_budget, RunStats};
 
 // Spotlight (tool) distance to look
 pub static DISTANCE:usize=4;
@@ -40,6 +86,8 @@ pub static DISTANCE:usize=4;
 // Distance to look with the "one_direction_view"
 pub static ONE_DIRECTION_DISTANCE:usize=8;
 pub static INFINITE:usize=10000;
+
+// Biggest world we can use: the generator is chosen with the configuration, but our structures have this size.
 static WORLD_SIZE:usize=500;
 
 // "DNA" of the set of the genetic algorithm. Basically how many steps/actions my set can do
@@ -70,6 +118,12 @@ lazy_static! {
 
     pub(crate) static ref ENVIRONMENT:Mutex<Option<EnvironmentalConditions>>=Mutex::new(Option::None);
 
+    //Cost of the sense of the paths, learned by the energy model and used by the threads.
+    pub(crate) static ref SENSE_COST:Mutex<usize>=Mutex::new(ONE_DIRECTION_DISTANCE*VIEW_COST_PER_TILE);
+
+    //Weather we predict for the next ticks, used by the threads to evaluate the future steps.
+    static ref STEP_FORECAST:Mutex<StepForecast>=Mutex::new(StepForecast::default());
+
     static ref POSITIONS_TO_GO:Mutex<Vec<PositionToGo>>=Mutex::new(PositionToGo::new());
 
     // Which directions my threads will decide to go. (based on the number of them I will launch the specific number of threads)
@@ -78,39 +132,109 @@ lazy_static! {
     // If we need to charge our robot because we don't have enough energy to do our stuff.
     static ref WAIT_FOR_ENERGY:Mutex<bool>=Mutex::new(false);
 
-    //Content we need to put in the crate/bin/bank
-    static ref CONTENT:Mutex<PutContent>=Mutex::new(PutContent::default());
+    //What we have in the backpack, used by the threads to decide where to put it.
+    static ref BACKPACK:Mutex<Vec<(Content,usize)>>=Mutex::new(Vec::new());
+
+    //The teleports we have activated, the threads can use them to jump.
+    static ref TELEPORTS:Mutex<Vec<(usize,usize)>>=Mutex::new(Vec::new());
+
+    //What we collect and where we put it, read once from the configuration.
+    //The tests don't depend on the arguments of cargo test or on the robot.conf of the folder.
+    pub(crate) static ref CONFIG:Config=if cfg!(test){Config::default()}else{Config::load()};
+
+    //Seed of the random numbers of the planners (see planner_rng), from the seed of the world.
+    //The recorded runs always have it, so "--compare" plans the same paths with the same code.
+    pub(crate) static ref PLANNER_SEED:Option<u64>={
+        let args:Vec<String>=std::env::args().collect();
+        if args.iter().any(|a| ["--seed","--record","--replay","--compare"].contains(&a.as_str())){Some(CONFIG.world.seed)}else{Option::None}
+    };
 
     // I use it for my robot map to tell the robot which blocks I have already visited
     static ref ALREADY_VISITED:Mutex<Vec<Vec<bool>>>=Mutex::new(Vec::new());
 
-}
+    //What the run did, for the benchmark. The robot updates it, main adds the time of the threads.
+    static ref STATS:Mutex<RunStats>=Mutex::new(RunStats::default());
 
-// Struct used when we have to insert a content inside a container
-struct PutContent{
-    content:Content,
-    quantity:usize,
-}
+    //Score of the world we are in, for the snapshots.
+    static ref WORLD_SCORE:Mutex<(f32,Option<HashMap<Content,f32>>)>=Mutex::new((0.0,Option::None));
 
+    //How many ticks we can do ("--ticks"), None if we continue until the planner fails.
+    static ref TICK_BUDGET:Option<usize>=tick_budget();
 
-impl Default for PutContent{
-    fn default() -> Self {
-        Self{
-            content:None,
-            quantity:0
-        }
-    }
+}
+
+// Why the executor stopped following the path
+#[derive(Debug,Clone,PartialEq)]
+enum ExecutionStop{
+    Finished,
+    // The path told us to wait a tick
+    Waiting,
+    NoEnergy,
+    // The action didn't work and the rest of the path doesn't make sense anymore (like a teleport)
+    Failed,
 }
 
 
 struct MyRobot{
     robot:Robot,
-    interest_points:HashMap<(usize,usize),Content>,
+    containers:ContainerRegistry,
+    markets:Markets,
+    energy_model:EnergyModel,
+    stuck_detector:StuckDetector,
+    event_log:EventLog,
+    forecast:Forecast,
+    // Contents we have already put in a tour, so we don't plan them again if we couldn't take them.
+    tour_visited:HashSet<(usize,usize)>,
+    // The path we are following is a tour
+    touring:bool,
+    coverage:CoveragePlanner,
+    roads:RoadPlanner,
+    search:ContentSearch,
+    audio:AudioFeedback,
+    view:TerminalView,
+    // Tiles we know from a previous run (see knowledge), the robot map of the world doesn't have them.
+    prior_map:Vec<Vec<Option<Tile>>>,
+    // How many tiles of ROBOT_MAP we know, we update it with the map
+    known_tiles:usize,
+    ticks:usize,
+    run_log:RunLog,
 }
 
 
 impl Runnable for MyRobot {
     fn process_tick(&mut self, world: &mut World) {
+        self.event_log.next_tick();
+        self.forecast.next_tick();
+        self.coverage.next_tick();
+        self.search.next_tick();
+        self.ticks+=1;
+        STATS.lock().unwrap().ticks=self.ticks;
+        self.run_log.next_tick();
+        if self.run_log.is_finished(){
+            println!("{:?} finished: {}",self.run_log.mode(),self.run_log.report());
+            std::process::exit(0);
+        }
+        if over_budget(){
+            self.end_of_comparison("the tick budget is over");
+        }
+        if self.ticks%SAVE_EVERY==0{
+            self.save_knowledge();
+        }
+        self.audio.observe_energy(self.get_energy().get_energy_level());
+
+        //The first time we create our map, then the events and the sensors update it
+        if ROBOT_MAP.lock().unwrap().is_empty(){
+            self.discover(world);
+        }
+
+        //In replay mode the plans start at the same tick of the recorded run
+        if *self.run_log.mode()==RunMode::Replay{
+            if let Some(plan)=self.run_log.next_plan(){
+                let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+                follow_dir.clear();
+                follow_dir.path_to_follow=plan;
+            }
+        }
 
         //Check if we have some issue with the energy, such as thread running or need energy to operate.
         if !self.is_energy_right(world){
@@ -122,20 +246,68 @@ impl Runnable for MyRobot {
 
         //We follow the path created by the threads (we basically move)
         if !follow_dir.path_to_follow.is_empty(){
+            //The threads can give us a path after is_energy_right, so it could be still without segments.
+            if follow_dir.segments.is_empty(){
+                self.split_path(&mut follow_dir);
+            }
+
+            //We only do the current segment, which we can pay with the energy we have.
+            let segment=match follow_dir.segments.first(){
+                Some(s) => s.clone(),
+                Option::None => return,
+            };
+            let from=follow_dir.next;
+            let path=follow_dir.path_to_follow[from..segment.end.max(from)].to_vec();
+
             //Actuator
-            self.move_based_on_threads(world,&mut follow_dir.path_to_follow);
+            let (executed,stop)=self.move_based_on_threads(world,&path);
+            follow_dir.next=from+executed;
 
-            // If we haven't finished walking, but we finish all the energy, we wait a cycle to recharge and continue
-            if follow_dir.is_done(){
-                follow_dir.path_to_follow.clear();
-                follow_dir.cost=0;
+            if stop==ExecutionStop::Failed{
+                STATS.lock().unwrap().failures+=1;
+
+                // We search a new path from where we are
+                follow_dir.clear();
+                self.touring=false;
 
                 *WAIT_FOR_ENERGY.lock().unwrap()=false;
-            }else{
-                let d=self.get_coordinate();
+            }else if follow_dir.is_done(){
+                follow_dir.clear();
+
+                //The tour is over, the contents we took aren't on the map anymore
+                if self.touring{
+                    self.tour_visited.clear();
+                    self.touring=false;
+                }
+
+                println!("{}",self.energy_model.report());
+
+                *WAIT_FOR_ENERGY.lock().unwrap()=false;
+            }else if follow_dir.next>=segment.end{
+                // We have finished the segment, so we are in the resume point. We wait here for the energy of the next one.
+                follow_dir.segments.remove(0);
+                if follow_dir.segments.is_empty(){
+                    self.split_path(&mut follow_dir);
+                }
+
+                let next_cost=follow_dir.segments.first().map_or(follow_dir.cost,|s| s.cost);
+                let level=self.get_energy().get_energy_level();
 
-                // This cost is used to calculate if our robot has enough energy to process all the actions
-                follow_dir.cost=calculate_cost_dir(&follow_dir,d.get_row(),d.get_col(),&robot_map(world).unwrap());
+                follow_dir.cost=next_cost;
+                follow_dir.wait_ticks=self.energy_model.ticks_to_recharge(level,next_cost);
+
+                println!("Segment done. Resume point:{:?} (safe:{}), waiting {} ticks for the next segment with cost:{}",segment.resume_point,segment.safe,follow_dir.wait_ticks,next_cost);
+
+                *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                return;
+            }else if stop==ExecutionStop::Waiting{
+                // We continue the segment the next tick
+                *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                return;
+            }else{
+                // The prediction was wrong and we finished the energy in the middle of the segment.
+                // We split again the rest of the path from where we are.
+                follow_dir.segments.clear();
 
                 *WAIT_FOR_ENERGY.lock().unwrap()=true;
                 return;
@@ -154,220 +326,755 @@ impl Runnable for MyRobot {
         POSITION.lock().unwrap().1=d.get_col();
 
 
+        //Every planning round, whatever we decide, ends with the terminal view.
+        self.planning_round(world);
+        self.draw_terminal_view();
+    }
 
+    fn handle_event(&mut self, event: Event) {
+        self.event_log.record(&event);
 
-        //Implement functions to go close to the bank/crate/bin:
-        //check backpack:
-        if self.backpack_contains_something(){
-            //We return the content and the size we need to leave in the specific container.
-            //This decision is based on a priority of which elements needs to get rid of.
-            // For example, if we have:
-            //          tree 15
-            //          rock 5
-            // We will try to get rid of the tree first, since it's the object which occupies most of the space.
-            let (content, mut size)=self.get_content_backpack();
-
-            //Content to search
-            println!("Content to search:{:?} and size:{}",content,size);
+        //We update our internal model with the event, instead of asking again to the world.
+        match &event{
+            Event::TimeChanged(environment) => {
+                ENVIRONMENT.lock().unwrap().replace(environment.clone());
+                self.forecast.observe(environment,false);
+            }
+            Event::DayChanged(environment) => {
+                ENVIRONMENT.lock().unwrap().replace(environment.clone());
+                self.forecast.observe(environment,true);
+            }
+            Event::EnergyRecharged(amount) => {
+                self.energy_model.observe_recharge(*amount);
+            }
+            Event::EnergyConsumed(amount) => {
+                STATS.lock().unwrap().energy_spent+=*amount;
+            }
+            Event::AddedToBackpack(content,quantity) => {
+                //If we are selling something, these are the coins we got (we didn't collect them)
+                if matches!(content,Coin(_)) && self.markets.is_selling(){
+                    self.markets.coins_received(*quantity);
+                }else{
+                    STATS.lock().unwrap().collected+=*quantity;
+                }
+                update_backpack(content,*quantity as i32);
+            }
+            Event::RemovedFromBackpack(content,quantity) => {
+                //Not every content that leaves the backpack is deposited (the market and the fire), see the put
+                update_backpack(content,-(*quantity as i32));
+            }
+            Event::Moved(tile,(x,y)) => {
+                self.update_known_tile(tile,*x,*y);
+                self.roads.record((*x,*y));
 
-            let search=self.search_respective_content(&content);
+                POSITION.lock().unwrap().0=*x;
+                POSITION.lock().unwrap().1=*y;
+            }
+            Event::TileContentUpdated(tile,(x,y)) => {
+                self.update_known_tile(tile,*x,*y);
+                self.update_interest_point(*x,*y,&tile.content);
+            }
+            Event::Terminated => {
+                println!("{}",self.event_log.summary());
+                self.event_log.flush(EVENT_LOG_FILE);
+                self.coverage.save(COVERAGE_FILE);
+                self.audio.save(AUDIO_FILE);
+                self.save_knowledge();
+                self.run_log.flush();
+                self.end_of_comparison("the run is over");
+            }
+            _ => {}
+        }
+    }
 
-            if search!=None && self.container_exists(&search){
+    fn get_energy(&self) -> &Energy {
+        &self.robot.energy
+    }
 
-                //We print our backpack contents
-                println!("We print what we have in the backpack");
-                println!("Size backpack:{}",self.get_backpack().get_size());
-                for i in self.get_backpack().get_contents().iter(){
-                    if i.0.to_default()==Rock(0).to_default() || i.0.to_default()==Coin(0) || i.0.to_default()==Garbage(0) || i.0.to_default()==Tree(0){
-                        println!("Backpack of {}:{}",i.0.to_default(),i.1);
-                    }
-                }
+    fn get_energy_mut(&mut self) -> &mut Energy {
+        &mut self.robot.energy
+    }
 
+    fn get_coordinate(&self) -> &Coordinate {
+        &self.robot.coordinate
+    }
 
-                // We search for the closest specific container for the content we need to get rid of
-                let (dest_x,dest_y)=self.search_content(search,d.get_row(),d.get_col(),&mut size);
+    fn get_coordinate_mut(&mut self) -> &mut Coordinate {
+        &mut self.robot.coordinate
+    }
 
-                let mut charted_path = ChartingTools::tool::<ChartedPaths>().unwrap();
-                charted_path.init(&robot_map(world).unwrap(), world);
+    fn get_backpack(&self) -> &BackPack {
+        &self.robot.backpack
+    }
 
-                let ch1=ChartedCoordinate::from((d.get_row(),d.get_col()));
-                let ch2=ChartedCoordinate::from((dest_x,dest_y));
+    fn get_backpack_mut(&mut self) -> &mut BackPack {
+        &mut self.robot.backpack
+    }
+}
 
+impl MyRobot{
+    fn new()->Self{
+        let mut vet=ALREADY_VISITED.lock().unwrap();
+        for _ in 0..WORLD_SIZE{
+            let mut v=Vec::new();
+            for _ in 0..WORLD_SIZE{
+                v.push(false);
+            }
+            vet.push(v);
+        }
+        drop(vet);
 
-                //We use the tool to search the best path to arrive at the container
-                let path=charted_path.shortest_path(ch1,ch2);
+        let mut robot=Self{
+            robot: Robot::new(),
+            containers: ContainerRegistry::default(),
+            markets: Markets::default(),
+            energy_model: EnergyModel::default(),
+            stuck_detector: StuckDetector::default(),
+            event_log: EventLog::default(),
+            forecast: Forecast::default(),
+            tour_visited: HashSet::new(),
+            touring: false,
+            coverage: CoveragePlanner::default(),
+            roads: RoadPlanner::default(),
+            search: ContentSearch::default(),
+            audio: AudioFeedback::from_args(),
+            view: TerminalView::default(),
+            prior_map: Vec::new(),
+            known_tiles: 0,
+            ticks: 0,
+            run_log: RunLog::from_args(),
+        };
+
+        if let Some(knowledge)=Knowledge::from_args(CONFIG.world.fingerprint()){
+            robot.restore_knowledge(knowledge);
+        }
+        robot
+    }
 
+    // We continue from what we knew at the end of a previous run.
+    fn restore_knowledge(&mut self,knowledge:Knowledge){
+        let mut visited=ALREADY_VISITED.lock().unwrap();
+        for (i,row) in knowledge.visited.iter().enumerate().take(visited.len()){
+            for (j,v) in row.iter().enumerate().take(visited[i].len()){
+                visited[i][j]=*v;
+            }
+        }
 
-                *CONTENT.lock().unwrap()=PutContent{
-                    content,quantity:size,
-                };
-                println!("(-------------------------------------------------------------------------)");
-                println!("Destination:({},{})",dest_x,dest_y);
-                println!("We print all the positions of our containers");
-                //We print all our "interesting point".
-                // They, basically, are the containers where we have to put all the objects
-                for i in &self.interest_points{
-                    println!("At this coordinate:({},{}) there is {:?}",i.0.0,i.0.1,i.1);
-                }
+        for (position,kind,capacity,failed_puts) in knowledge.containers{
+            self.containers.restore(position,kind,capacity,failed_puts);
+        }
+        for (position,trades) in knowledge.markets{
+            self.markets.observe(position,&Market(trades));
+        }
+        for (position,traversals) in knowledge.roads{
+            self.roads.restore(position,traversals);
+        }
+        self.roads.paved(&knowledge.paved);
 
-                if path.is_some(){
+        self.prior_map=knowledge.tiles;
+    }
 
-                    let path=path.unwrap();
+    // What we know now, for the next run.
+    fn save_knowledge(&self){
+        //ALREADY_VISITED is as big as WORLD_SIZE, we only save the part of the world
+        let tiles=ROBOT_MAP.lock().unwrap().clone();
+        let size=tiles.len();
+        let visited=ALREADY_VISITED.lock().unwrap().iter().take(size).map(|row| row.iter().take(size).cloned().collect()).collect();
+
+        let knowledge=Knowledge{
+            world:CONFIG.world.fingerprint().unwrap_or(String::from("random")),
+            tiles,
+            visited,
+            containers:self.containers.all().map(|(p,c)| (*p,c.kind.clone(),c.capacity,c.failed_puts)).collect(),
+            markets:self.markets.trades().map(|(p,n)| (*p,*n)).collect(),
+            roads:self.roads.traversals().map(|(p,n)| (*p,*n)).collect(),
+            paved:self.roads.paved_tiles().cloned().collect(),
+        };
+
+        let filename=Knowledge::filename();
+        if let Err(e)=knowledge.save(&filename){
+            println!("Error saving the knowledge:{}",e);
+        }
+    }
 
-                    FOLLOW_DIRECTIONS.lock().unwrap().cost=path.0;
+    // We write the action in the run log. If we are replaying and the result is different, we stop at the first difference.
+    fn log_action(&mut self,action:String,result:&str){
+        if *self.run_log.mode()==RunMode::Normal{return}
 
-                    let mut result_path=InputDir::convert_to_input_dir(d.get_row() as i32,d.get_col() as i32,path.1);
+        let d=self.get_coordinate();
+        let position=(d.get_row(),d.get_col());
+        let energy=self.get_energy().get_energy_level();
+
+        if let Some(divergence)=self.run_log.action(action,result,energy,position){
+            println!("First divergence at the action {} (tick {}):",divergence.index,divergence.actual.tick);
+            println!("    expected:{:?}",divergence.expected);
+            println!("    actual:  {:?}",divergence.actual);
+            std::process::exit(1);
+        }
+    }
 
-                    let dir=result_path.pop().unwrap();
-                    let dir=match dir{
-                        InputDir::Right(_, _) => {InputDir::Right(false,true)}
-                        InputDir::Left(_, _) => {InputDir::Left(false,true)}
-                        InputDir::Top(_, _) => {InputDir::Top(false,true)}
-                        InputDir::Bottom(_, _) => {InputDir::Bottom(false,true)}
-                        InputDir::None => {InputDir::None}
-                    };
-                    result_path.push(dir);
+    // The run ends: in replay and compare mode it's a success only if we did all the recorded actions.
+    fn end_of_comparison(&mut self,reason:&str){
+        if !matches!(self.run_log.mode(),RunMode::Replay | RunMode::Compare){return}
 
-                    println!("Charted path found with cost:{}\n\n\n",path.0);
+        if self.run_log.is_finished(){
+            println!("{:?} finished: {}",self.run_log.mode(),self.run_log.report());
+            std::process::exit(0);
+        }
+        println!("{:?} not finished ({}): {}",self.run_log.mode(),reason,self.run_log.report());
+        std::process::exit(2);
+    }
 
-                    FOLLOW_DIRECTIONS.lock().unwrap().path_to_follow=result_path;
-                    *WAIT_FOR_ENERGY.lock().unwrap()=true;
-                    return;
+    // The calls the planners did to the world at this tick of the recorded run, instead of asking the planners.
+    // The plans are started at the beginning of the tick (see process_tick).
+    fn replay_calls(&mut self,world:&mut World){
+        while let Some(call)=self.run_log.next_call(){
+            match call{
+                Call::Sense(call) => {
+                    self.sensor_call(world,&call);
+                    //And the other calls of the same sensing plan
+                    self.sense(world);
                 }
-                else{
-                    println!("Error with the creation of path with charted path");
-                    return;
+                Call::Pave(direction,length) => {
+                    let d=self.get_coordinate();
+                    let corridor=Corridor::straight((d.get_row(),d.get_col()),direction,length);
+                    self.pave(world,&corridor);
+                }
+                Call::Search(content,distance) => {
+                    self.search_content(world,&content,distance);
                 }
             }
         }
 
+        //Without a path and without decisions nothing else can happen
+        if self.run_log.decisions_over(){
+            self.end_of_comparison("the recorded decisions are over");
+        }
+    }
 
-        //I visualize the new area I have just moved in
+    // We do exactly what the path says. It returns how many actions we have done and why we stopped.
+    fn move_based_on_threads(&mut self,world:&mut World,path:&[Action])->(usize,ExecutionStop){
 
-        let res_visualize=self.visualize_around(world);
-        match res_visualize{
-            Ok(_)=>{},
-            Err(e)=>{
+        let environment=ENVIRONMENT.lock().unwrap().clone();
 
-                if e==NotEnoughEnergy{
+        let mut executed=0;
+        let mut stop=ExecutionStop::Finished;
 
-                *WAIT_FOR_ENERGY.lock().unwrap()=true;
+        for i in path.iter(){
+
+            //We save the energy level before the action, so the energy model can compare it with its prediction.
+            let energy_before=self.get_energy().get_energy_level();
+            // What the action gave back, for the run log, and if we stop after it
+            let mut result=String::from("Ok");
+            let mut last=false;
 
+            match i{
+                Action::Wait => {
+                    stop=ExecutionStop::Waiting;
+                    last=true;
                 }
+                Action::Sense => {
+                    self.sense(world);
+                }
+                Action::Teleport(destination) => {
+                    let predicted=self.energy_model.predict_teleport();
+                    match teleport(self,world,*destination){
+                        Ok(_) => {
+                            let energy_after=self.get_energy().get_energy_level();
+                            self.energy_model.observe(ActionKind::Teleport,predicted,energy_before,energy_after);
+                        }
+                        Err(e) => {
+                            println!("Error teleport:{:?}",e);
+                            result=format!("{:?}",e);
+                            stop=if e==NotEnoughEnergy{ExecutionStop::NoEnergy}else{ExecutionStop::Failed};
+                            last=true;
+                        }
+                    }
+                }
+                Action::Destroy(dir) => {
+                    let predicted=self.predict_destroy(dir);
+                    let d=destroy(self,world,dir.property());
+                    result=format!("{:?}",d);
+                    if d==Err(NotEnoughEnergy){
+                        stop=ExecutionStop::NoEnergy;
+                        last=true;
+                    }else{
+                        let energy_after=self.get_energy().get_energy_level();
+                        self.energy_model.observe(ActionKind::Destroy,predicted,energy_before,energy_after);
+                    }
+                }
+                Action::Put(dir,content,quantity) => {
+                    let predicted=self.energy_model.predict_put(content,*quantity);
+                    let (i,j)=direction_value(dir);
+                    let coordinate=self.get_coordinate();
+                    let container=((coordinate.get_row() as i32+i) as usize,(coordinate.get_col() as i32+j) as usize);
+
+                    let selling=self.markets.is_market(container);
+                    if selling{
+                        self.markets.start_sale(content,*quantity);
+                    }
 
-                println!("Operation not allowed:{:?}",e);return;
-            },
+                    let d=put(self, world, content.clone(), *quantity, dir.property());
+                    println!("{:?}",d);
+
+                    if selling{
+                        self.markets.end_sale(*d.as_ref().unwrap_or(&0));
+                    }
+
+                    result=format!("{:?}",d);
+                    if d==Err(NotEnoughEnergy){
+                        stop=ExecutionStop::NoEnergy;
+                        last=true;
+                    }else{
+                        //We update the capacity we estimate for the container
+                        self.containers.record_put(container,match &d{Ok(n) => Ok(*n),Err(_) => Err(())});
+                        if !selling && matches!(d,Ok(n) if n>0){
+                            STATS.lock().unwrap().deposited+=*d.as_ref().unwrap_or(&0);
+                            self.audio.play(Cue::Deposit);
+                        }
+
+                        let energy_after=self.get_energy().get_energy_level();
+                        self.energy_model.observe(ActionKind::Put,predicted,energy_before,energy_after);
+                        self.save_contents();
+                    }
+                }
+                Action::Move(dir) => {
+                    //We never walk in the fire: we extinguish it, or we search another path.
+                    if self.content_towards(dir)==Some(Fire) && !self.extinguish(world,dir){
+                        result=String::from("Fire");
+                        stop=ExecutionStop::Failed;
+                        last=true;
+                    }else{
+                        let predicted=self.predict_move(dir,&environment);
+                        let d=go(self,world,dir.property());
+                        match d{
+                            Ok(_) => {
+                                let energy_after=self.get_energy().get_energy_level();
+                                self.energy_model.observe(ActionKind::Move,predicted,energy_before,energy_after);
+                            }
+                            Err(e) => {
+                                println!("Error move:{:?}",e);
+                                result=format!("{:?}",e);
+                                //If error we return and we wait when we will have more energy
+                                if e==NotEnoughEnergy{
+                                    stop=ExecutionStop::NoEnergy;
+                                    last=true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.log_action(action_to_string(i),&result);
+
+            if last{
+                //The wait is done, the actions that didn't work aren't
+                if stop==ExecutionStop::Waiting{
+                    executed+=1;
+                }
+                break;
+            }
+            executed+=1;
         }
 
+        //Walking we see the tiles around us
+        self.discover(world);
 
-        //Function for the map image:
-        self.visualize_robot_map(world);
+        //We set the coordinate we arrived as true, so we won't go here again.
+        let x=self.get_coordinate().get_row();
+        let y=self.get_coordinate().get_col();
 
-        //I upload the new static data, which they will be used by the threads.
-        self.update_static_data(world);
-        self.save_contents(world);
 
+        //We set the area we have arrived as visited (the world can be smaller than WORLD_SIZE).
+        let size=ROBOT_MAP.lock().unwrap().len().min(WORLD_SIZE);
+        for i in -2..=2{
+            let x1=x as i32+i;
+            if x1<0 || x1>=size as i32{continue}
 
-    }
+            for j in -2..=2{
+                let y1=y as i32+j;
+                if y1<0 || y1>=size as i32{continue}
 
-    fn handle_event(&mut self, event: Event) { /*println!("{:?}", event); */}
+                ALREADY_VISITED.lock().unwrap()[x1 as usize][y1 as usize]=true;
+            }
+        }
 
-    fn get_energy(&self) -> &Energy {
-        &self.robot.energy
+        (executed,stop)
     }
 
-    fn get_energy_mut(&mut self) -> &mut Energy {
-        &mut self.robot.energy
-    }
+    // We use the tool to search the best path between the two points.
+    // If we know some teleports, we also try to walk to the closest one and jump close to the destination.
+    fn charted_path(&self,world:&World,from:(usize,usize),to:(usize,usize))->Option<(usize,Vec<Action>)>{
+        let map=self.known_map()?;
+
+        let mut charted_path = ChartingTools::tool::<ChartedPaths>().unwrap();
+        charted_path.init(&map, world);
+
+        let shortest=|a:(usize,usize),b:(usize,usize)|->Option<(usize,Vec<Action>)>{
+            if a==b{return Some((0,Vec::new()))}
+            let path=charted_path.shortest_path(ChartedCoordinate::from(a),ChartedCoordinate::from(b))?;
+            Some((path.0,InputDir::convert_to_actions(a.0 as i32,a.1 as i32,path.1)))
+        };
+
+        let direct=shortest(from,to);
+
+        let teleports=known_teleports(&map);
+        let closest=|p:(usize,usize)|{
+            teleports.iter().min_by_key(|t| (t.0 as i32-p.0 as i32).abs()+(t.1 as i32-p.1 as i32).abs()).cloned()
+        };
+
+        let via_teleport=match (closest(from),closest(to)){
+            (Some(t1),Some(t2)) if t1!=t2 => {
+                match (shortest(from,t1),shortest(t2,to)){
+                    (Some((c1,mut p1)),Some((c2,p2))) => {
+                        p1.push(Action::Teleport(t2));
+                        p1.extend(p2);
+                        Some((c1+self.energy_model.predict_teleport()+c2,p1))
+                    }
+                    _ => Option::None,
+                }
+            }
+            _ => Option::None,
+        };
 
-    fn get_coordinate(&self) -> &Coordinate {
-        &self.robot.coordinate
+        match (direct,via_teleport){
+            (Some(d),Some(t)) => if t.0<d.0{Some(t)}else{Some(d)},
+            (d,t) => d.or(t),
+        }
     }
 
-    fn get_coordinate_mut(&mut self) -> &mut Coordinate {
-        &mut self.robot.coordinate
-    }
+    // Path to the tile next to "to", where we do the action (made with the direction of the last move).
+    // It returns the cost, the path and where the robot will be at the end.
+    fn leg_with_action(&self,world:&World,from:(usize,usize),to:(usize,usize),action:impl Fn(InputDir)->Action)->Option<(usize,Vec<Action>,(usize,usize))>{
+        let (cost,mut leg)=self.charted_path(world,from,to)?;
 
-    fn get_backpack(&self) -> &BackPack {
-        &self.robot.backpack
+        let dir=match leg.pop(){
+            Some(Action::Move(dir)) => dir,
+            _ => return Option::None,
+        };
+        leg.push(action(dir));
+
+        let (i,j)=direction_value(&dir);
+        Some((cost,leg,((to.0 as i32-i) as usize,(to.1 as i32-j) as usize)))
     }
 
-    fn get_backpack_mut(&mut self) -> &mut BackPack {
-        &mut self.robot.backpack
+    // We sell what we have the most of (between what the policy lets us sell) in the closest market.
+    fn plan_sale(&mut self,world:&World)->bool{
+        let d=self.get_coordinate();
+        let start=(d.get_row(),d.get_col());
+
+        let market=match self.markets.nearest(start){
+            Some(m) => m,
+            Option::None => return false,
+        };
+
+        let sale=self.get_backpack().get_contents().iter()
+            .filter(|(content,quantity)| CONFIG.policy.sells(content) && **quantity>=CONFIG.policy.threshold(content))
+            .max_by_key(|(_,quantity)| **quantity)
+            .map(|(content,quantity)| (content.clone(),*quantity));
+
+        let (content,quantity)=match sale{
+            Some(s) => s,
+            Option::None => return false,
+        };
+
+        let (path_cost,path,_)=match self.leg_with_action(world,start,market,|dir| Action::Put(dir,content.clone(),quantity)){
+            Some(l) => l,
+            Option::None => return false,
+        };
+
+        if !self.markets.worth_selling(&content,quantity,path_cost){return false}
+
+        println!("We sell {} {:?} in the market at {:?} (price:{:.1})",quantity,content,market,self.markets.price(&content));
+
+        let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+        follow_dir.clear();
+        follow_dir.path_to_follow=path;
+        follow_dir.cost=path_cost;
+
+        *WAIT_FOR_ENERGY.lock().unwrap()=true;
+        true
     }
-}
 
-impl MyRobot{
-    fn new()->Self{
-        let mut vet=ALREADY_VISITED.lock().unwrap();
-        for _ in 0..WORLD_SIZE{
-            let mut v=Vec::new();
-            for _ in 0..WORLD_SIZE{
-                v.push(false);
-            }
-            vet.push(v);
+    // We order the contents to collect and the containers in a tour, then we expand every leg with the path finder.
+    fn plan_tour(&mut self,world:&World)->bool{
+        let map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return false,
+        };
+
+        let d=self.get_coordinate();
+        let start=(d.get_row(),d.get_col());
+
+        let visited=&self.tour_visited;
+        let backpack=BACKPACK.lock().unwrap().clone();
+        let stops=tour_stops(&map,&self.containers,&self.markets,start.0,start.1,&|c| content_use(c,&backpack)==ContentUse::Collect,&|p| visited.contains(&p));
+        if stops.len()<TOUR_MIN_STOPS{return false}
+
+        let mut load=HashMap::new();
+        let mut used=0;
+        for (content,quantity) in self.get_backpack().get_contents().iter(){
+            if *quantity==0{continue}
+            load.insert(content.to_default(),*quantity);
+            used+=*quantity;
         }
-        Self{
-            robot: Robot::new(),
-            interest_points: HashMap::new(),
+        let free_space=self.get_backpack().get_size().saturating_sub(used);
+
+        let problem=TourProblem::new(start,stops,free_space,load,MAX_ENERGY).with_prices(self.markets.prices());
+        if problem.useful_stops()<TOUR_MIN_STOPS{return false}
+        seed_planner(&[self.ticks as u64]);
+        let mut visits=problem.solve();
+        let mut order:Vec<usize>=visits.iter().map(|v| v.stop).collect();
+
+        let mut path=Vec::new();
+        let mut cost=0;
+        let mut position=start;
+        let mut k=0;
+
+        while k<visits.len(){
+            let visit=visits[k].clone();
+            let stop=&problem.stops[visit.stop];
+
+            //We can't walk on the stop, so the last move becomes the action on it
+            let leg=match (&stop.kind,&visit.put){
+                (StopKind::Collect(_),_) => self.leg_with_action(world,position,stop.position,Action::Destroy),
+                (StopKind::Deposit(_,_),Some((content,quantity))) | (StopKind::Sell,Some((content,quantity))) => self.leg_with_action(world,position,stop.position,|dir| Action::Put(dir,content.clone(),*quantity)),
+                _ => Option::None,
+            };
+
+            let (leg_cost,leg,next)=match leg{
+                Some(l) => l,
+                Option::None => {
+                    //We can't arrive there: the loads of the next stops change, so we compute them again without it.
+                    //The visits before this one stay the same.
+                    order.retain(|s| *s!=visit.stop);
+                    visits=problem.visits(&order);
+                    continue;
+                }
+            };
+
+            if let StopKind::Collect(_)=stop.kind{
+                self.tour_visited.insert(stop.position);
+            }
+
+            position=next;
+            cost+=leg_cost;
+            path.extend(leg);
+            k+=1;
         }
+
+        if path.is_empty(){return false}
+
+        println!("Tour of {} stops with cost:{}",visits.len(),cost);
+        self.touring=true;
+
+        let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+        follow_dir.clear();
+        follow_dir.path_to_follow=path;
+        follow_dir.cost=cost;
+
+        *WAIT_FOR_ENERGY.lock().unwrap()=true;
+        true
     }
 
-    fn move_based_on_threads(&mut self,world:&mut World,path:&mut Vec<InputDir>){
-
-        let cont=CONTENT.lock().unwrap().content.clone();
-        let quantity=CONTENT.lock().unwrap().quantity.clone();
-
-        for i in path.iter_mut(){
-            if *i==InputDir::None{continue}
-
-            match *i{
-                InputDir::Right(true,_) => {let _=destroy(self,world,i.property());},
-                InputDir::Left(true,_) => {let _=destroy(self,world,i.property());}
-                InputDir::Top(true,_) => {let _=destroy(self,world,i.property());}
-                InputDir::Bottom(true,_) => {let _=destroy(self,world,i.property());}
-                InputDir::Right(_,true) => {let d=put(self, world, cont.clone(), quantity, Direction::Right);println!("{:?}",d);self.save_contents(world);},
-                InputDir::Left(_,true) => {let d=put(self, world,  cont.clone(), quantity, Direction::Left);println!("{:?}",d);self.save_contents(world);}
-                InputDir::Top(_,true) => {let d=put(self, world, cont.clone(), quantity, Direction::Up);println!("{:?}",d);self.save_contents(world);}
-                InputDir::Bottom(_,true) => {let d=put(self, world, cont.clone(), quantity, Direction::Down);println!("{:?}",d);self.save_contents(world);}
-                _ => {
-                    let d=go(self,world,i.property());
-                    match d{
-                        Ok(_) => {}
-                        Err(e) => {println!("Error move:{:?}",e); if e==NotEnoughEnergy{return;} /*If error we return and we wait when we will have more energy*/}
-                    }
+    // We go to the next point of the sweep. If the sweep is over, the threads explore as before.
+    fn plan_coverage(&mut self,world:&World)->bool{
+        let map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return false,
+        };
+
+        let coverage=self.coverage.record(&map);
+
+        let d=self.get_coordinate();
+        let (x,y)=(d.get_row(),d.get_col());
+
+        while let Some(waypoint)=self.coverage.next_waypoint(&map,x,y){
+            match self.charted_path(world,(x,y),waypoint){
+                Some((path_cost,path)) if !path.is_empty() => {
+                    println!("Coverage:{:.1}%. Next point of the sweep:{:?} with cost:{}",coverage*100.0,waypoint,path_cost);
+
+                    let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+                    follow_dir.clear();
+                    follow_dir.path_to_follow=path;
+                    follow_dir.cost=path_cost;
+
+                    *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                    return true;
                 }
+                //We can't arrive there, we try the next one
+                _ => {}
             }
+        }
 
-            *i=InputDir::None;
+        println!("Coverage:{:.1}%. There is nothing else we can reach",coverage*100.0);
+        false
+    }
+
+    // If we are stuck, we forbid the places where we have been and we go to the farthest frontier we know.
+    fn escape_if_stuck(&mut self,world:&World)->bool{
+        let d=self.get_coordinate();
+        let x=d.get_row();
+        let y=d.get_col();
+
+        let reason=self.stuck_detector.new_round((x,y),self.known_tiles);
+        if reason.is_none(){
+            return false;
         }
 
-        //We set the coordinate we arrived as true, so we won't go here again.
-        let x=self.get_coordinate().get_row();
-        let y=self.get_coordinate().get_col();
+        let map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return false,
+        };
 
+        println!("The robot is stuck ({:?}). We try to escape",reason.unwrap());
+        self.audio.play(Cue::Stuck);
+        self.stuck_detector.escape();
 
-        //We set the area we have arrived as visited.
-        for i in -2..=2{
-            let mut x1=x as i32+i;
+        let frontier=self.stuck_detector.farthest_frontier(&map,x,y);
+        if frontier.is_none(){
+            return false;
+        }
+
+        match self.charted_path(world,(x,y),frontier.unwrap()){
+            Some((path_cost,path)) => {
+                println!("Escape to the frontier {:?} with cost:{}",frontier.unwrap(),path_cost);
 
-            if x1<0{
-                x1=0;
-            }else if x1>WORLD_SIZE as i32{
-                x1=WORLD_SIZE as i32;
+                let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+                follow_dir.clear();
+                follow_dir.path_to_follow=path;
+                follow_dir.cost=path_cost;
+
+                *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                true
             }
+            Option::None => false,
+        }
+    }
 
-            for j in -2..=2{
-                let mut y1=y as i32+j;
-                if y1<0{
-                    y1=0;
-                }else if y1>= WORLD_SIZE as i32 {
-                    y1=WORLD_SIZE as i32;
-                }
-                ALREADY_VISITED.lock().unwrap()[x1 as usize][y1 as usize]=true;
+    // Prediction of the next move, done on the map used by the threads.
+    fn predict_move(&self,dir:&InputDir,environment:&Option<EnvironmentalConditions>)->usize{
+        if environment.is_none(){return 0}
+
+        let d=self.get_coordinate();
+        let (i,j)=Action::Move(*dir).target();
+        let next_x=d.get_row() as i32+i;
+        let next_y=d.get_col() as i32+j;
+
+        let map=ROBOT_MAP.lock().unwrap();
+        if next_x<0 || next_y<0 || next_x as usize>=map.len() || next_y as usize>=map.len(){return 0}
+
+        self.energy_model.predict_move(&map,(d.get_row(),d.get_col()),(next_x as usize,next_y as usize),environment.as_ref().unwrap())
+    }
+
+    fn predict_destroy(&self,dir:&InputDir)->usize{
+        let d=self.get_coordinate();
+        let (i,j)=Action::Destroy(*dir).target();
+        let next_x=d.get_row() as i32+i;
+        let next_y=d.get_col() as i32+j;
+
+        let map=ROBOT_MAP.lock().unwrap();
+        if next_x<0 || next_y<0 || next_x as usize>=map.len() || next_y as usize>=map.len(){return 0}
+
+        match &map[next_x as usize][next_y as usize]{
+            Some(tile) => self.energy_model.predict_destroy(&tile.content),
+            Option::None => 0,
+        }
+    }
+
+    fn content_towards(&self,dir:&InputDir)->Option<Content>{
+        let d=self.get_coordinate();
+        let (i,j)=Action::Move(*dir).target();
+        let next_x=d.get_row() as i32+i;
+        let next_y=d.get_col() as i32+j;
+
+        let map=ROBOT_MAP.lock().unwrap();
+        if next_x<0 || next_y<0 || next_x as usize>=map.len() || next_y as usize>=map.len(){return Option::None}
+
+        map[next_x as usize][next_y as usize].as_ref().map(|tile| tile.content.clone())
+    }
+
+    // We pave the corridor next to us that pays back the most, if we have the rocks and the energy.
+    fn build_road(&mut self,world:&mut World)->bool{
+        let d=self.get_coordinate();
+        let (x,y)=(d.get_row(),d.get_col());
+
+        let rocks=self.get_backpack().get_contents().get(&Rock(0)).cloned().unwrap_or(0);
+        let environment=ENVIRONMENT.lock().unwrap().clone();
+
+        let corridor={
+            let map=ROBOT_MAP.lock().unwrap();
+            if map.is_empty(){return false}
+            self.roads.best_corridor(&map,x,y,rocks,environment.as_ref())
+        };
+
+        let corridor=match corridor{
+            Some(c) => c,
+            Option::None => return false,
+        };
+
+        if !self.get_energy().has_enough_energy(corridor.cost()+SEGMENT_RESERVE){return false}
+
+        println!("We pave {} tiles {:?} (payback:{})",corridor.tiles.len(),corridor.direction,corridor.payback());
+        self.pave(world,&corridor)
+    }
+
+    // The tool puts a rock on every tile of the project, starting next to the robot.
+    fn pave(&mut self,world:&mut World,corridor:&Corridor)->bool{
+        let mut asphaltinator=Asphaltinator::new();
+        let project=asphaltinator.design_project(Shape::LongLong(corridor.tiles.len() as u32,corridor.direction.clone()));
+
+        let call=Call::Pave(corridor.direction.clone(),corridor.tiles.len());
+        self.run_log.call(&call);
+
+        let result=asphaltinator.asfalting(self,world,project);
+        self.discover(world);
+        self.log_action(call_to_string(&call),&match &result{Ok(_) => String::from("Ok"),Err(e) => format!("{:?}",e)});
+
+        //Only the tiles that became streets are paved, we don't try again the others
+        let (paved,failed):(Vec<(usize,usize)>,Vec<(usize,usize)>)={
+            let map=ROBOT_MAP.lock().unwrap();
+            corridor.tiles.iter().cloned().partition(|(x,y)| matches!(map.get(*x).and_then(|row| row.get(*y)),Some(Some(t)) if t.tile_type==TileType::Street))
+        };
+        self.roads.paved(&paved);
+        self.roads.failed(&failed);
+
+        match result{
+            Ok(_) => failed.is_empty(),
+            Err(e) => {
+                println!("Error building the road:{:?}",e);
+                false
             }
         }
+    }
+
+    // We put water on the fire in that direction. False if we don't have water, or it didn't work.
+    fn extinguish(&mut self,world:&mut World,dir:&InputDir)->bool{
+        let backpack:Vec<(Content,usize)>=self.get_backpack().get_contents().iter().map(|(c,q)| (c.clone(),*q)).collect();
+        if quantity_in(&backpack,&Water(0))==0{
+            println!("Fire on the path and no water to extinguish it");
+            return false;
+        }
+
+        let energy_before=self.get_energy().get_energy_level();
+        let predicted=self.energy_model.predict_put(&Water(0),1);
+
+        let result=put(self,world,Water(0),1,dir.property());
+        self.log_action(format!("X:{:?}",dir),&format!("{:?}",result));
 
+        match result{
+            Ok(_) => {
+                let energy_after=self.get_energy().get_energy_level();
+                self.energy_model.observe(ActionKind::Put,predicted,energy_before,energy_after);
+                println!("Fire extinguished");
+                true
+            }
+            Err(e) => {
+                println!("Error extinguishing the fire:{:?}",e);
+                false
+            }
+        }
     }
 
     fn get_from_to(&self,distance:usize,len:i32)->Vec<(i32,i32)>{
@@ -387,10 +1094,11 @@ impl MyRobot{
         vec![(from_x,to_x),(from_y,to_y)]
     }
 
-    fn save_contents(&mut self, world:&World){
-        if robot_map(world).is_none(){return}
-
-        let map=robot_map(world).unwrap();
+    fn save_contents(&mut self){
+        let map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return,
+        };
 
         let v=self.get_from_to(ONE_DIRECTION_DISTANCE,map.len() as i32);
 
@@ -400,53 +1108,141 @@ impl MyRobot{
 
                 let content=&map[i as usize][j as usize].as_ref().unwrap().content;
 
-                if content.to_default()==None || content.to_default()==Fire || content.to_default()==Tree(0) || content.to_default()==Bush(0) || content.to_default()==Fish(0) || content.to_default()==Rock(0) || content.to_default()==Coin(0) || content.to_default()==Garbage(0) || content.to_default()==Market(0){continue}
-
-                else{
-                    if match content{
-                        Bin(a) => {if a.end-a.start==0{false}else{true}}
-                        Crate(a) => {if a.end-a.start==0{false}else{true}}
-                        Bank(a) => {if a.end-a.start==0{false}else{true}}
-                        _ => {true}
-                    }{
+                self.update_interest_point(i as usize,j as usize,content);
+            }
+        }
 
-                        self.interest_points.insert((i as usize,j as usize), content.clone());
+    }
 
-                    }else{
+    // We save the container in this position, or we forget it if it isn't there anymore.
+    fn update_interest_point(&mut self,x:usize,y:usize,content:&Content){
+        self.containers.observe((x,y),content);
+        self.markets.observe((x,y),content);
+    }
 
-                        self.interest_points.remove(&(i as usize,j as usize));
+    // We change a single tile of the map used by the threads.
+    fn update_known_tile(&mut self,tile:&Tile,x:usize,y:usize){
+        let mut map=ROBOT_MAP.lock().unwrap();
+        if x<map.len() && y<map[x].len(){
+            if map[x][y].is_none(){
+                self.known_tiles+=1;
+            }
+            map[x][y]=Some(tile.clone());
+        }
+    }
 
-                    }
+    // The sensors and the moves show new tiles, but there isn't an event for them: we take them from the robot map.
+    // We only add the tiles we didn't know, the changes of the tiles we know arrive with the events.
+    fn discover(&mut self,world:&World){
+        let new=match robot_map(world){
+            Some(m) => m,
+            Option::None => return,
+        };
+
+        let mut map=ROBOT_MAP.lock().unwrap();
+        if map.len()!=new.len(){
+            //The first time we start from what we knew in a previous run, if it is the same world
+            *map=if self.prior_map.len()==new.len(){std::mem::take(&mut self.prior_map)}else{vec![vec![Option::None;new.len()];new.len()]};
+            self.known_tiles=known_tiles(&map);
+        }
 
+        for (i,row) in new.into_iter().enumerate(){
+            for (j,tile) in row.into_iter().enumerate(){
+                if tile.is_some() && j<map[i].len() && map[i][j].is_none(){
+                    map[i][j]=tile;
+                    self.known_tiles+=1;
                 }
             }
         }
+    }
 
+    // Our copy of the map, for the planners.
+    fn known_map(&self)->Option<Vec<Vec<Option<Tile>>>>{
+        let map=ROBOT_MAP.lock().unwrap();
+        if map.is_empty(){Option::None}else{Some(map.clone())}
     }
 
-    fn update_static_data(&self,world:&World){
-        ENVIRONMENT.lock().unwrap().replace(look_at_sky(world));
+    fn update_static_data(&mut self,world:&World){
+        //After the first time, the environment is updated by the events.
+        if ENVIRONMENT.lock().unwrap().is_none(){
+            let environment=look_at_sky(world);
+            self.forecast.observe(&environment,false);
+            ENVIRONMENT.lock().unwrap().replace(environment);
+        }
 
+        //The threads will use the predicted weather for the steps in the future
+        *STEP_FORECAST.lock().unwrap()=self.forecast.step_forecast(self.energy_model.recharge_per_tick());
 
-        //I clear my old view
-        ROBOT_MAP.lock().unwrap().clear();
-        let map=robot_map(world);
+        //What we have in the backpack (BACKPACK) and the map of the threads (ROBOT_MAP) are updated by the events and the sensors.
+        *TELEPORTS.lock().unwrap()=known_teleports(&ROBOT_MAP.lock().unwrap());
+        STATS.lock().unwrap().tiles_discovered=self.known_tiles;
 
-        if map.is_none(){return;}
+    }
 
-        let mut m=map.unwrap();
+    //We discover new tiles around us.
+    //The planner decides which sensor calls are worth it, based on the unseen tiles they show and their cost.
+    fn sense(&mut self,world:&mut World){
+        //In replay mode the sensing planner doesn't run, we do the recorded calls
+        if *self.run_log.mode()==RunMode::Replay{
+            while let Some(call)=self.run_log.next_sense(){
+                self.sensor_call(world,&call);
+            }
+            self.discover(world);
+            return;
+        }
 
-        //I copy the new view into my ROBOT_MAP, used for explore threads.
-        ROBOT_MAP.lock().unwrap().append(&mut m);
+        let rob_map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return,
+        };
 
+        let d=self.get_coordinate();
+        let x=d.get_row();
+        let y=d.get_col();
+
+        let spotlight_cost=Spotlight::calculate_illuminate_cost(self,world,DISTANCE).unwrap_or(INFINITE);
+        let budget=self.get_energy().get_energy_level().saturating_sub(SENSING_RESERVE);
+
+        let mut planner=SensingPlanner::new(DISTANCE,spotlight_cost,ONE_DIRECTION_DISTANCE,&self.energy_model);
+        let sensing_plan=planner.plan(&rob_map,x,y,budget);
+
+        for option in sensing_plan.iter(){
+            self.sensor_call(world,&option.call);
+        }
+
+        self.discover(world);
+    }
+
+    // A single call to one of our sensors. The energy model compares its cost with the prediction.
+    fn sensor_call(&mut self,world:&mut World,call:&SensorCall){
+        let spotlight_cost=match call{
+            SensorCall::Spotlight(distance) => Spotlight::calculate_illuminate_cost(self,world,*distance).unwrap_or(INFINITE),
+            SensorCall::OneDirection(_,_) => 0,
+        };
+        let predicted=self.energy_model.predict_sense(call,spotlight_cost);
+        let energy_before=self.get_energy().get_energy_level();
+
+        let logged=Call::Sense(call.clone());
+        self.run_log.call(&logged);
+
+        let result=match call{
+            SensorCall::Spotlight(distance) => Spotlight::illuminate(self,world,*distance).map(|_| ()).map_err(|e| format!("{:?}",e)),
+            SensorCall::OneDirection(dir,distance) => one_direction_view(self,world,dir.clone(),*distance).map(|_| ()).map_err(|e| format!("{:?}",e)),
+        };
+
+        let energy_after=self.get_energy().get_energy_level();
+        self.energy_model.observe_sense(call,predicted,energy_before,energy_after);
+        *SENSE_COST.lock().unwrap()=self.energy_model.predict_view();
+        self.log_action(call_to_string(&logged),&format!("{:?}",result));
     }
 
     fn visualize_around(&mut self,world:&mut World)->Result<(),LibError>{
         POSITIONS_TO_GO.lock().unwrap().clear();
 
-        if robot_map(world).is_none(){return Err(OperationNotAllowed);}
-
-        let rob_map=robot_map(world).unwrap();
+        let rob_map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return Err(OperationNotAllowed),
+        };
 
         let d=self.get_coordinate();
         let x=d.get_row();
@@ -456,25 +1252,14 @@ impl MyRobot{
         let res_vet =PositionToGo::new_with_world(&rob_map, x, y);
 
 
-        let _=Spotlight::illuminate(self,world,DISTANCE);
-
-
         let mut result=Vec::new();
 
 
         //We discover new tiles around us
-        for i in res_vet.iter().enumerate(){
-             match i.1{
-                 PositionToGo::Right => {if !is_not_visualize(x as i32, y as i32+ONE_DIRECTION_DISTANCE as i32){let _=one_direction_view(self, world, Direction::Right, ONE_DIRECTION_DISTANCE);}},
-                 PositionToGo::Down => {if !is_not_visualize(x as i32+ONE_DIRECTION_DISTANCE as i32, y as i32){let _=one_direction_view(self, world, Direction::Down, ONE_DIRECTION_DISTANCE);}},
-                 PositionToGo::Left => {if !is_not_visualize(x as i32, y as i32-ONE_DIRECTION_DISTANCE as i32){let _=one_direction_view(self, world, Direction::Left, ONE_DIRECTION_DISTANCE);}}
-                 PositionToGo::Top => {if !is_not_visualize(x as i32-ONE_DIRECTION_DISTANCE as i32, y as i32){let _=one_direction_view(self, world, Direction::Up, ONE_DIRECTION_DISTANCE);}}
-                 _=>{},
-             }
-        }
+        self.sense(world);
 
-        //We take the new robot map with the updated robot map
-        let rob_map=robot_map(world).unwrap();
+        //We take our map with the new tiles
+        let rob_map=self.known_map().unwrap();
 
 
         // We try an iteration without selecting the one with shallowWater. (my politics)
@@ -485,42 +1270,42 @@ impl MyRobot{
             //We second conditions is set because we don't want to set a point to go which is "Lava", "DeepWater" or Tile=None.
             match i.1{
                 PositionToGo::Right => {
-                    if !is_not_visualize(x as i32, y as i32+ONE_DIRECTION_DISTANCE as i32) && is_good_tile(&rob_map[x][y+ONE_DIRECTION_DISTANCE]) && rob_map[x][y+ONE_DIRECTION_DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32, y as i32+ONE_DIRECTION_DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x][y+ONE_DIRECTION_DISTANCE]) && rob_map[x][y+ONE_DIRECTION_DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::DownRight => {
-                    if !is_not_visualize(x as i32+DISTANCE as i32, y as i32+DISTANCE as i32) && is_good_tile(&rob_map[x+DISTANCE][y+DISTANCE]) && rob_map[x+DISTANCE][y+DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32+DISTANCE as i32, y as i32+DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x+DISTANCE][y+DISTANCE]) && rob_map[x+DISTANCE][y+DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::Down => {
-                    if !is_not_visualize(x as i32+ONE_DIRECTION_DISTANCE as i32, y as i32) && is_good_tile(&rob_map[x+ONE_DIRECTION_DISTANCE][y]) && rob_map[x+ONE_DIRECTION_DISTANCE][y].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32+ONE_DIRECTION_DISTANCE as i32, y as i32,rob_map.len()) && is_good_tile(&rob_map[x+ONE_DIRECTION_DISTANCE][y]) && rob_map[x+ONE_DIRECTION_DISTANCE][y].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::TopRight => {
-                    if !is_not_visualize(x as i32-DISTANCE as i32,y as i32+DISTANCE as i32) && is_good_tile(&rob_map[x-DISTANCE][y+DISTANCE]) && rob_map[x-DISTANCE][y+DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32-DISTANCE as i32,y as i32+DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x-DISTANCE][y+DISTANCE]) && rob_map[x-DISTANCE][y+DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::Left => {
-                    if !is_not_visualize(x as i32, y as i32-ONE_DIRECTION_DISTANCE as i32) && is_good_tile(&rob_map[x][y-ONE_DIRECTION_DISTANCE]) && rob_map[x][y-ONE_DIRECTION_DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32, y as i32-ONE_DIRECTION_DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x][y-ONE_DIRECTION_DISTANCE]) && rob_map[x][y-ONE_DIRECTION_DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::TopLeft => {
-                    if !is_not_visualize(x as i32-DISTANCE as i32, y as i32-DISTANCE as i32) && is_good_tile(&rob_map[x-DISTANCE][y-DISTANCE]) && rob_map[x-DISTANCE][y-DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32-DISTANCE as i32, y as i32-DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x-DISTANCE][y-DISTANCE]) && rob_map[x-DISTANCE][y-DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::Top => {
-                    if !is_not_visualize(x as i32-ONE_DIRECTION_DISTANCE as i32, y as i32) && is_good_tile(&rob_map[x-ONE_DIRECTION_DISTANCE][y]) && rob_map[x-ONE_DIRECTION_DISTANCE][y].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32-ONE_DIRECTION_DISTANCE as i32, y as i32,rob_map.len()) && is_good_tile(&rob_map[x-ONE_DIRECTION_DISTANCE][y]) && rob_map[x-ONE_DIRECTION_DISTANCE][y].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 },
                 PositionToGo::DownLeft => {
-                    if !is_not_visualize(x as i32+DISTANCE as i32, y as i32-DISTANCE as i32) && is_good_tile(&rob_map[x+DISTANCE][y-DISTANCE]) && rob_map[x+DISTANCE][y-DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
+                    if !is_not_visualize(x as i32+DISTANCE as i32, y as i32-DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x+DISTANCE][y-DISTANCE]) && rob_map[x+DISTANCE][y-DISTANCE].as_ref().unwrap().tile_type!=ShallowWater{
                         result.push(i.1.clone());
                     }
                 }
@@ -536,42 +1321,42 @@ impl MyRobot{
 
                 match i.1{
                     PositionToGo::Right => {
-                        if !is_not_visualize(x as i32, y as i32+ONE_DIRECTION_DISTANCE as i32) && is_good_tile(&rob_map[x][y+ONE_DIRECTION_DISTANCE]){
+                        if !is_not_visualize(x as i32, y as i32+ONE_DIRECTION_DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x][y+ONE_DIRECTION_DISTANCE]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::DownRight => {
-                        if !is_not_visualize(x as i32+DISTANCE as i32, y as i32+DISTANCE as i32) && is_good_tile(&rob_map[x+DISTANCE][y+DISTANCE]){
+                        if !is_not_visualize(x as i32+DISTANCE as i32, y as i32+DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x+DISTANCE][y+DISTANCE]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::Down => {
-                        if !is_not_visualize(x as i32+ONE_DIRECTION_DISTANCE as i32, y as i32) && is_good_tile(&rob_map[x+ONE_DIRECTION_DISTANCE][y]){
+                        if !is_not_visualize(x as i32+ONE_DIRECTION_DISTANCE as i32, y as i32,rob_map.len()) && is_good_tile(&rob_map[x+ONE_DIRECTION_DISTANCE][y]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::TopRight => {
-                        if !is_not_visualize(x as i32-DISTANCE as i32,y as i32+DISTANCE as i32) && is_good_tile(&rob_map[x-DISTANCE][y+DISTANCE]){
+                        if !is_not_visualize(x as i32-DISTANCE as i32,y as i32+DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x-DISTANCE][y+DISTANCE]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::Left => {
-                        if !is_not_visualize(x as i32, y as i32-ONE_DIRECTION_DISTANCE as i32) && is_good_tile(&rob_map[x][y-ONE_DIRECTION_DISTANCE]){
+                        if !is_not_visualize(x as i32, y as i32-ONE_DIRECTION_DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x][y-ONE_DIRECTION_DISTANCE]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::TopLeft => {
-                        if !is_not_visualize(x as i32-DISTANCE as i32, y as i32-DISTANCE as i32) && is_good_tile(&rob_map[x-DISTANCE][y-DISTANCE]){
+                        if !is_not_visualize(x as i32-DISTANCE as i32, y as i32-DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x-DISTANCE][y-DISTANCE]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::Top => {
-                        if !is_not_visualize(x as i32-ONE_DIRECTION_DISTANCE as i32, y as i32) && is_good_tile(&rob_map[x-ONE_DIRECTION_DISTANCE][y]){
+                        if !is_not_visualize(x as i32-ONE_DIRECTION_DISTANCE as i32, y as i32,rob_map.len()) && is_good_tile(&rob_map[x-ONE_DIRECTION_DISTANCE][y]){
                             result.push(i.1.clone());
                         }
                     },
                     PositionToGo::DownLeft => {
-                        if !is_not_visualize(x as i32+DISTANCE as i32, y as i32-DISTANCE as i32) && is_good_tile(&rob_map[x+DISTANCE][y-DISTANCE]){
+                        if !is_not_visualize(x as i32+DISTANCE as i32, y as i32-DISTANCE as i32,rob_map.len()) && is_good_tile(&rob_map[x+DISTANCE][y-DISTANCE]){
                             result.push(i.1.clone());
                         }
                     }
@@ -581,108 +1366,291 @@ impl MyRobot{
 
 
 
-        // We prevent some possible problem. Since, it might get stuck in the threads to search for
-        // the best path. So we travel in places which we have already visited before.
-        if result.len()==0{
-            result=PositionToGo::new_already_seen(&rob_map,x,y);
+        // We prevent some possible problem. Since, it might get stuck in the threads to search for
+        // the best path. So we travel in places which we have already visited before.
+        if result.len()==0{
+            result=PositionToGo::new_already_seen(&rob_map,x,y);
+        }
+
+        //We don't go back to the places where we were stuck, if we have other choices.
+        let allowed:Vec<PositionToGo>=result.iter().filter(|p|{
+            let (ds_x,ds_y)=get_next_position((*p).clone());
+            let destination_x=x as i32+ds_x;
+            let destination_y=y as i32+ds_y;
+            destination_x<0 || destination_y<0 || !self.stuck_detector.is_forbidden(destination_x as usize,destination_y as usize)
+        }).cloned().collect();
+
+        if !allowed.is_empty(){
+            result=allowed;
+        }
+
+        *POSITIONS_TO_GO.lock().unwrap()=result.clone();
+
+        return Ok(());
+    }
+
+    fn enough_energy_to_operate(&mut self, moves:&MovesToFollow,world:&World)->bool{
+        // We are going to calculate if we have enough energy to do all our operations
+
+        let d=self.get_coordinate();
+        let mut x=d.get_row();
+        let mut y=d.get_col();
+
+
+        let rob_map=match self.known_map(){
+            Some(m) => m,
+            Option::None => return false,
+        };
+
+        //We only need the energy for the current segment of the path. It was predicted by our energy model.
+        let mut cost=match moves.segments.first(){
+            Some(segment) => segment.cost,
+            Option::None => moves.cost,
+        };
+
+        for i in &moves.path_to_follow[moves.next..]{
+            match i{
+                Action::Teleport(destination) => {
+                    x=destination.0;
+                    y=destination.1;
+                }
+                Action::Move(_) => {
+                    let d=i.target();
+
+                    x=(x as i32+d.0) as usize;
+                    y=(y as i32+d.1) as usize;
+                }
+                _ => {}
+            }
+        }
+
+
+        //Check energy costs:
+        //We will look around only at the end of the path, so only when we are in the last segment.
+        if moves.segments.len()<=1{
+            //We ask the sensing planner what it would like to do once we have arrived, with the costs predicted by the energy model.
+            let spotlight_cost=Spotlight::calculate_illuminate_cost(self,world,DISTANCE).unwrap_or(INFINITE);
+            let mut planner=SensingPlanner::new(DISTANCE,spotlight_cost,ONE_DIRECTION_DISTANCE,&self.energy_model);
+            let cost_energy=SensingPlanner::total_cost(&planner.plan(&rob_map,x,y,INFINITE));
+
+            cost+=cost_energy;
+        }
+
+        //Every segment can be done with a full charge, so we never wait for more than that.
+        self.get_energy().has_enough_energy(cost.min(MAX_ENERGY))
+    }
+
+    fn visualize_robot_map(&mut self,world:&mut World){
+        //I use the debug function to show the global map only. I never use it for calculation purpose
+        let v=debug(self,world).0;
+        let mut new:Vec<Vec<Option<Tile>>>=Vec::new();
+        for i in v{
+            let mut vet:Vec<Option<Tile>>=Vec::new();
+            for j in i{
+                vet.push(Some(j));
+            }
+            new.push(vet);
         }
 
-        *POSITIONS_TO_GO.lock().unwrap()=result.clone();
+        //I create the graphical image of the map (total view)
+        export_of_image::export_to_image(&new,"mappa.jpg",self);
 
-        return Ok(());
+
+        //I upload the new image of what I have seen
+        let v=ROBOT_MAP.lock().unwrap().clone();
+        export_of_image::export_to_image(&v,"visualize.jpg",self);
     }
 
-    fn enough_energy_to_operate(&mut self, moves:&MovesToFollow,world:&World)->bool{
-        // We are going to calculate if we have enough energy to do all our operations
+    // If somebody created SNAPSHOT_REQUEST, we save the world as it is now (the robot is the spawn point).
+    fn snapshot_if_requested(&mut self,world:&mut World){
+        if std::fs::remove_file(SNAPSHOT_REQUEST).is_err(){return}
 
+        let tiles=debug(self,world).0;
         let d=self.get_coordinate();
-        let mut x=d.get_row();
-        let mut y=d.get_col();
+        let (weather,progression)=self.forecast.weather_sequence();
+
+        let (max_score,score_table)=WORLD_SCORE.lock().unwrap().clone();
+        let file=WorldFile::new(tiles,(d.get_row(),d.get_col()),&look_at_sky(world),max_score,score_table)
+            .with_weather(weather,progression.min(u8::MAX as u32) as u8);
+
+        match file.save(SNAPSHOT_FILE){
+            Ok(_) => println!("World saved in {}",SNAPSHOT_FILE),
+            Err(e) => println!("Error saving the world:{}",e),
+        }
+    }
 
+    // We decide what to do next: a tour, a deposit, a sale, a road, an escape or the exploration.
+    fn planning_round(&mut self,world:&mut World){
+        let d=self.get_coordinate();
 
-        if robot_map(world).is_none(){return false;}//return Err(OperationNotAllowed);}
+        //In replay mode the decisions come from the run log, our planners don't run.
+        if *self.run_log.mode()==RunMode::Replay{
+            self.replay_calls(world);
+            self.update_static_data(world);
+            return;
+        }
 
-        let rob_map=robot_map(world).unwrap();
+        //We try to collect the contents around us and to empty the backpack with a single tour.
+        if TOUR_PLANNING && self.plan_tour(world){
+            return;
+        }
 
-        //We calculate the cost of the follor_dir variable path. (It's the path we created with the threads)
-        let mut cost=moves.cost;
 
+        //Implement functions to go close to the bank/crate/bin:
+        //check backpack:
+        if self.backpack_contains_something(){
+            //We return the content and the size we need to leave in the specific container.
+            //This decision is based on a priority of which elements needs to get rid of.
+            // For example, if we have:
+            //          tree 15
+            //          rock 5
+            // We will try to get rid of the tree first, since it's the object which occupies most of the space.
+            let (content, size)=self.get_content_backpack();
 
-        //I initialize the vector also used by the threads, which they will find the best path to it
-        let res_vet=PositionToGo::new_with_world(&rob_map,x,y);
+            //Content to search
+            println!("Content to search:{:?} and size:{}",content,size);
+
+            let search=self.search_respective_content(&content);
+
+            if search!=None && self.containers.exists(&search){
+
+                //We print our backpack contents
+                println!("We print what we have in the backpack");
+                println!("Size backpack:{}",self.get_backpack().get_size());
+                for i in self.get_backpack().get_contents().iter(){
+                    if i.0.to_default()==Rock(0).to_default() || i.0.to_default()==Coin(0) || i.0.to_default()==Garbage(0) || i.0.to_default()==Tree(0){
+                        println!("Backpack of {}:{}",i.0.to_default(),i.1);
+                    }
+                }
+
+                // We search the containers for the content we need to get rid of.
+                // If no one can take everything, we split the load.
+                let start=(d.get_row(),d.get_col());
+                let deposits=self.containers.plan_deposit(&search,size,start);
+
+                println!("(-------------------------------------------------------------------------)");
+                println!("Deposits:{:?}",deposits);
+                println!("{}",self.containers.report());
+
+                let mut path_cost=0;
+                let mut result_path=Vec::new();
+                let mut position=start;
+
+                for (container,quantity) in deposits{
+                    //We use the tool to search the best path to arrive at the container
+                    match self.leg_with_action(world,position,container,|dir| Action::Put(dir,content.clone(),quantity)){
+                        Some((leg_cost,leg,next)) => {
+                            path_cost+=leg_cost;
+                            result_path.extend(leg);
+                            position=next;
+                        }
+                        Option::None => break,
+                    }
+                }
 
-        for i in &moves.path_to_follow{
-            if *i==InputDir::None{continue}
+                if !result_path.is_empty(){
+                    println!("Charted path found with cost:{}\n\n\n",path_cost);
 
-            let d=direction_value(&i);
+                    let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+                    follow_dir.cost=path_cost;
+                    follow_dir.path_to_follow=result_path;
 
-            x=(x as i32+d.0) as usize;
-            y=(y as i32+d.1) as usize;
+                    *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                    return;
+                }
+                else{
+                    println!("Error with the creation of path with charted path");
+                    return;
+                }
+            }
+        }
 
 
+        //The backpack is filling and we don't know where to put something: we search the container with the tool.
+        if self.search_missing_container(world){
+            return;
         }
 
 
-        //Check energy costs:
-        let mut cost_energy =0;
-        let mut flag=false;
+        //If we have something to sell and the market is worth the trip, we go there.
+        if self.plan_sale(world){
+            return;
+        }
 
-        for i in &res_vet{
 
-            cost_energy+=match i{
-                PositionToGo::Down => ONE_DIRECTION_DISTANCE*3,
-                PositionToGo::Right => ONE_DIRECTION_DISTANCE*3,
-                PositionToGo::Top => ONE_DIRECTION_DISTANCE*3,
-                PositionToGo::Left => ONE_DIRECTION_DISTANCE*3,
-                _=>{flag=true; 0},
-            };
+        //If we walk the same corridor many times, we pave it with our rocks.
+        if BUILD_ROADS && self.build_road(world){
+            return;
         }
 
-        if flag{
-            //Only problem with the calculation of the total cost:
-            let mut sp=Spotlight::calculate_illuminate_cost(self,world,DISTANCE).unwrap();
-            //The spotlight cost isn't correct. idk why
 
-            //For manage the spotlight cost error I put a bigger limit to it
-            if sp<200{
-                sp=200;
-            }
+        //We check if we are moving in circles, or if we aren't discovering anything new.
+        if self.escape_if_stuck(world){
+            return;
+        }
+
+
+        //I visualize the new area I have just moved in
+
+        let res_visualize=self.visualize_around(world);
+        match res_visualize{
+            Ok(_)=>{},
+            Err(e)=>{
+
+                if e==NotEnoughEnergy{
 
-            cost_energy+=sp;
+                *WAIT_FOR_ENERGY.lock().unwrap()=true;
+
+                }
+
+                println!("Operation not allowed:{:?}",e);return;
+            },
         }
 
-        cost+=cost_energy;
 
-        if self.get_energy().get_energy_level()>800 && cost>1000{
-            // If the cost is over 1000 and we have at least 800 energy, we will do some operations until we have finished all our energy.
-            // but then, we will wait again here, so we can conclude everything we need to do.
-            //An example it's when we used the path finder and the position we need to reach it's really far from us.
-            println!("Entered because cost energy>1000");
-            true
-        }else{
-            self.get_energy().has_enough_energy(cost)
+        //Function for the map image:
+        self.visualize_robot_map(world);
+        self.snapshot_if_requested(world);
+
+        //We write the events of this round
+        self.event_log.flush(EVENT_LOG_FILE);
+
+        //I upload the new static data, which they will be used by the threads.
+        self.update_static_data(world);
+        self.save_contents();
+
+        //In coverage mode we decide where to go, instead of the threads.
+        if CONFIG.goal==Goal::Coverage{
+            self.plan_coverage(world);
         }
     }
 
-    fn visualize_robot_map(&mut self,world:&mut World){
-        //I use the debug function to show the global map only. I never use it for calculation purpose
-        let v=debug(self,world).0;
-        let mut new:Vec<Vec<Option<Tile>>>=Vec::new();
-        for i in v{
-            let mut vet:Vec<Option<Tile>>=Vec::new();
-            for j in i{
-                vet.push(Some(j));
-            }
-            new.push(vet);
-        }
+    // The map we know, the path we are following and what the robot is doing, in the terminal.
+    fn draw_terminal_view(&self){
+        if !self.view.is_enabled(){return}
 
-        //I create the graphical image of the map (total view)
-        export_of_image::export_to_image(&new,"mappa.jpg",self);
+        let d=self.get_coordinate();
+        let follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
 
+        let status=if !follow_dir.path_to_follow.is_empty(){
+            format!("Following a path ({}/{} actions, cost:{})",follow_dir.next,follow_dir.path_to_follow.len(),follow_dir.cost)
+        }else if *WAIT_FOR_ENERGY.lock().unwrap(){
+            String::from("Waiting for energy")
+        }else{
+            format!("Exploring ({:?})",CONFIG.goal)
+        };
 
-        //I upload the new image of what I have seen
-        let v=robot_map(world).unwrap();
-        export_of_image::export_to_image(&v,"visualize.jpg",self);
+        let state=ViewState{
+            position:(d.get_row(),d.get_col()),
+            energy:self.get_energy().get_energy_level(),
+            backpack:self.get_backpack().get_contents().iter().filter(|(_,q)| **q>0).map(|(c,q)| (c.clone(),*q)).collect(),
+            status,
+        };
+
+        let containers:HashSet<(usize,usize)>=self.containers.usable().map(|(p,_)| *p).chain(self.markets.positions().cloned()).collect();
+        let path=&follow_dir.path_to_follow[follow_dir.next.min(follow_dir.path_to_follow.len())..];
+
+        self.view.draw(&ROBOT_MAP.lock().unwrap(),path,&containers,&state);
     }
 
     fn is_energy_right(&mut self, world:&mut World) ->bool{
@@ -690,7 +1658,36 @@ impl MyRobot{
         if *RECHARGE.lock().unwrap(){
             return false;
         }
-        let follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+        let mut follow_dir=FOLLOW_DIRECTIONS.lock().unwrap();
+
+        //New path: we split it in segments we can do with a single charge.
+        if !follow_dir.path_to_follow.is_empty() && follow_dir.segments.is_empty(){
+            //With the run log we write the plan
+            if follow_dir.next==0{
+                self.run_log.plan(&follow_dir.path_to_follow);
+            }
+
+            self.split_path(&mut follow_dir);
+            self.audio.play(Cue::PlanFound);
+
+            //If the weather later makes the path much cheaper, we wait for it before starting.
+            let d=self.get_coordinate();
+            let map=ROBOT_MAP.lock().unwrap();
+            let forecast=STEP_FORECAST.lock().unwrap();
+
+            follow_dir.wait_ticks=best_wait(&follow_dir.path_to_follow[follow_dir.next..],d.get_row(),d.get_col(),&map,&forecast);
+            if follow_dir.wait_ticks>0{
+                println!("We wait {} ticks for a better weather",follow_dir.wait_ticks);
+            }
+        }
+
+        //We are waiting in a resume point. The recharge is learned by the energy model with the events.
+        if follow_dir.wait_ticks>0{
+            follow_dir.wait_ticks-=1;
+
+            *WAIT_FOR_ENERGY.lock().unwrap()=true;
+            return false;
+        }
 
 
         // Do we need to do some free cycles because we don't have enough energy?
@@ -707,100 +1704,195 @@ impl MyRobot{
         true
     }
 
-    fn get_content_backpack(&self)->(Content,usize){
-        let mut max_so_far:usize=0;
-        let mut cont=None;
-
-        for i in self.get_backpack().get_contents().iter(){
-            if i.0.to_default()==Garbage(0) && *i.1>=5{
-                return (i.0.clone(),*i.1);
-            }else if (i.0.to_default()==Garbage(0).to_default()|| i.0.to_default()==Coin(0) || i.0.to_default()==Tree(0)) && *i.1>max_so_far{
-                max_so_far=*i.1;
-                cont=i.0.clone();
+    fn split_path(&self,moves:&mut MovesToFollow){
+        let d=self.get_coordinate();
+        let x=d.get_row();
+        let y=d.get_col();
+
+        let environment=ENVIRONMENT.lock().unwrap().clone();
+        let map=self.known_map();
+
+        if environment.is_some() && map.is_some(){
+            let map=map.unwrap();
+
+            //We only split what we haven't done yet
+            let steps=self.energy_model.predict_steps(&moves.path_to_follow[moves.next..],x,y,&map,environment.as_ref().unwrap());
+            moves.segments=split_in_segments(&steps,(x,y),&map,MAX_ENERGY-SEGMENT_RESERVE);
+
+            for segment in moves.segments.iter_mut(){
+                segment.start+=moves.next;
+                segment.end+=moves.next;
             }
         }
-        (cont,max_so_far)
+
+        //We couldn't predict anything, so we follow the whole path as before.
+        if moves.segments.is_empty(){
+            moves.segments.push(PathSegment{
+                start:moves.next,
+                end:moves.path_to_follow.len(),
+                cost:moves.cost,
+                resume_point:(x,y),
+                safe:false,
+            });
+        }
+
+        moves.cost=moves.segments[0].cost;
+
+        if moves.segments.len()>1{
+            println!("Path split in {} segments. Resume points:{:?}",moves.segments.len(),moves.segments.iter().map(|s| s.resume_point).collect::<Vec<_>>());
+        }
     }
 
-    fn search_content(&self,content:Content,x:usize,y:usize,size:&mut usize)->(usize,usize){
+    // What we want to put in a container now, and how much.
+    // The contents over their threshold come first (in the order of priority), then the one we have the most of.
+    fn get_content_backpack(&self)->(Content,usize){
+        let policy=&CONFIG.policy;
 
-        let mut res_x=INFINITE;
-        let mut res_y=INFINITE;
+        let mut best:Option<(&Content,usize)>=Option::None;
+        let mut best_key=(true,usize::MAX,0);
 
-        for i in self.interest_points.iter(){
-            if i.1.to_default()==content{
-                if (x.abs_diff(i.0.0)+(y.abs_diff(i.0.1)))<(x.abs_diff(res_x)+y.abs_diff(res_y)){
+        for (content,quantity) in self.get_backpack().get_contents().iter(){
+            if *quantity==0 || policy.container_for(content)==None{continue}
 
-                    //We are making this condition because:
-                    //If the garbage size of the backpack is bigger than 5, it won't fit all in the bin
-                    //So we put at max 5 garbage in the bin
-                    if i.1.get_value().1.unwrap().end-i.1.get_value().1.unwrap().start<*size{
-                        *size=i.1.get_value().1.unwrap().end-i.1.get_value().1.unwrap().start;
-                    }
+            let key=(*quantity<policy.threshold(content),policy.priority(content),usize::MAX-*quantity);
+            if best.is_none() || key<best_key{
+                best=Some((content,*quantity));
+                best_key=key;
+            }
+        }
 
-                    res_x=i.0.0;
-                    res_y=i.0.1;
-                }
+        match best{
+            Some((content,quantity)) => {
+                let quantity=match policy.batch(content){
+                    Some(batch) => quantity.min(batch),
+                    Option::None => quantity,
+                };
+                (content.clone(),quantity)
             }
+            Option::None => (None,0),
         }
+    }
 
-        (res_x,res_y)
+    fn search_respective_content(&self,content:&Content)->Content{
+        CONFIG.policy.container_for(content)
     }
 
-    fn container_exists(&self,content:&Content)->bool{
-        for i in self.interest_points.iter(){
-            if i.1.to_default()==*content{
-                return true;
+    // The first content (by priority) that is half of its threshold and has no container we know.
+    fn missing_container(&self)->Option<Content>{
+        let mut contents:Vec<(&Content,&usize)>=self.get_backpack().get_contents().iter().collect();
+        contents.sort_by_key(|(c,_)| CONFIG.policy.priority(c));
+
+        for (content,quantity) in contents{
+            let container=self.search_respective_content(content);
+            if container!=None && *quantity*2>=CONFIG.policy.threshold(content) && !self.containers.exists(&container){
+                return Some(container);
             }
         }
-        false
+        Option::None
     }
 
-    fn search_respective_content(&self,content:&Content)->Content{
-        match content {
-            Tree(_)=>{Crate(0..0)}
-            Garbage(_) => {Bin(0..0)}
-            Coin(_) => {Bank(0..0)}
-            _=>{None}
+    // We sense a square around us with sense_and_find and we save the containers it finds.
+    fn search_missing_container(&mut self,world:&mut World)->bool{
+        let container=match self.missing_container(){
+            Some(c) => c,
+            Option::None => return false,
+        };
+
+        let distance=match self.search.next_search(&container){
+            Some(d) => d,
+            Option::None => return false,
+        };
+
+        if !self.get_energy().has_enough_energy(search_cost(distance)+SENSING_RESERVE){return false}
+
+        self.search_content(world,&container,distance);
+        true
+    }
+
+    // We search the content with the tool, and we remember the containers we find.
+    fn search_content(&mut self,world:&mut World,container:&Content,distance:usize){
+        let found=self.find_content(world,container,distance);
+        println!("Search of {:?} in a radius of {}: found {:?}",container,distance,found);
+        self.search.record(container,distance,found.len());
+
+        for (x,y) in found.iter(){
+            if let Some(tile)=ROBOT_MAP.lock().unwrap().get(*x).and_then(|row| row.get(*y)).cloned().flatten(){
+                self.update_interest_point(*x,*y,&tile.content);
+            }
+        }
+    }
+
+    // Positions of the content in the square around us, after the tool has discovered the tiles.
+    fn find_content(&mut self,world:&mut World,content:&Content,distance:usize)->Vec<(usize,usize)>{
+        let mut lssf=Lssf::new();
+
+        let call=Call::Search(content.clone(),distance);
+        self.run_log.call(&call);
+
+        match lssf.smart_sensing_centered(distance,world,self,SEARCH_GRANULARITY){
+            Ok(_) => {
+                //The tool discovered new tiles, so we update our copy of the map
+                self.discover(world);
+                let found=lssf.get_content_vec(content);
+                self.log_action(call_to_string(&call),&format!("Ok({})",found.len()));
+                found
+            }
+            Err(e) => {
+                self.log_action(call_to_string(&call),&format!("{:?}",e));
+                println!("Error searching {:?}:{:?}",content,e);
+                if e==NotEnoughEnergy{
+                    *WAIT_FOR_ENERGY.lock().unwrap()=true;
+                }
+                Vec::new()
+            }
         }
     }
 
+    // We go to a container when we have enough of something (for the policy) and we know where to put it.
     fn backpack_contains_something(&self)->bool{
         let map=self.get_backpack().get_contents();
-        let mut max:usize =0;
         for i in map{
-            if *i.1>max && self.container_exists(&self.search_respective_content(i.0)){
-                max=*i.1;
+            let container=self.search_respective_content(i.0);
+            if container!=None && *i.1>=CONFIG.policy.threshold(i.0) && self.containers.exists(&container){
+                return true;
             }
         }
-        if max>5{
-            true
-        }else{
-            false
-        }
+        false
     }
 }
 
 struct MovesToFollow{
-    path_to_follow:Vec<InputDir>,
+    path_to_follow:Vec<Action>,
+    // Index of the next action we have to do
+    next:usize,
     cost:usize,
+    // Pieces of the path we can do with one charge, see path_segments.
+    segments:Vec<PathSegment>,
+    // Ticks we still have to wait in the resume point before the next segment.
+    wait_ticks:usize,
 }
 
 impl MovesToFollow{
     fn new()->Self{
         MovesToFollow{
             path_to_follow:Vec::new(),
+            next:0,
             cost:0,
+            segments:Vec::new(),
+            wait_ticks:0,
         }
     }
 
+    fn clear(&mut self){
+        self.path_to_follow.clear();
+        self.next=0;
+        self.cost=0;
+        self.segments.clear();
+        self.wait_ticks=0;
+    }
+
     fn is_done(&self)->bool{
-        for i in self.path_to_follow.iter(){
-            if *i!=InputDir::None{
-                return false;
-            }
-        }
-        true
+        self.next>=self.path_to_follow.len()
     }
 
 }
@@ -851,7 +1943,7 @@ impl PositionToGo{
             let destination_y=(y as i32)+ds_y;
 
 
-            if is_not_visualize(destination_x, destination_y){ continue }
+            if is_not_visualize(destination_x, destination_y,rob_map.len()){ continue }
 
             if rob_map[destination_x as usize][destination_y as usize].is_none(){
                 v.push(position);
@@ -882,7 +1974,7 @@ impl PositionToGo{
             let destination_y=(y as i32)+ds_y;
 
 
-            if is_not_visualize(destination_x, destination_y)
+            if is_not_visualize(destination_x, destination_y,rob_map.len())
                 || (
                 rob_map[destination_x as usize][destination_y as usize].is_some()
                 && rob_map[destination_x as usize][destination_y as usize].as_ref().unwrap().tile_type==ShallowWater
@@ -905,7 +1997,7 @@ impl PositionToGo{
                 let destination_y=(y as i32)+ds_y;
 
 
-                if is_not_visualize(destination_x, destination_y) { continue }
+                if is_not_visualize(destination_x, destination_y,rob_map.len()) { continue }
 
                 if is_good_tile(&rob_map[destination_x as usize][destination_y as usize]) && !already_visited(destination_x,destination_y){
                     v.push(position);
@@ -938,7 +2030,7 @@ impl PositionToGo{
             let destination_y=(y as i32)+ds_y;
 
 
-            if is_not_visualize(destination_x, destination_y){ continue }
+            if is_not_visualize(destination_x, destination_y,rob_map.len()){ continue }
 
             if is_good_tile(&rob_map[destination_x as usize][destination_y as usize]){
                 v.push(position);
@@ -951,29 +2043,100 @@ impl PositionToGo{
 }
 
 
+// The backpack used by the threads, updated with the events (a negative quantity is what left the backpack).
+fn update_backpack(content:&Content,quantity:i32){
+    let mut backpack=BACKPACK.lock().unwrap();
+    let key=content.to_default();
+
+    match backpack.iter().position(|(c,_)| c.to_default()==key){
+        Some(i) => {
+            let left=(backpack[i].1 as i32+quantity).max(0) as usize;
+            if left==0{
+                backpack.remove(i);
+            }else{
+                backpack[i].1=left;
+            }
+        }
+        Option::None if quantity>0 => backpack.push((key,quantity as usize)),
+        Option::None => {}
+    }
+}
+
+// The tick budget is finished.
+fn over_budget()->bool{
+    TICK_BUDGET.map_or(false,|budget| STATS.lock().unwrap().ticks>=budget)
+}
+
+// We write the summary of the run, if somebody asked it (the batch does).
+fn finish_run(end:&str){
+    let mut stats=STATS.lock().unwrap();
+    stats.end=end.to_string();
+    println!("Run finished ({}) after {} ticks",end,stats.ticks);
+
+    if let Some(filename)=summary_file(){
+        stats.save(&filename);
+    }
+}
+
 fn main() {
 
+    //In batch mode we only start the other runs and we write the table.
+    if is_batch(){
+        run_batch();
+        return;
+    }
+
     let r = MyRobot::new();
 
-    let mut g = WorldGenerator::new(WORLD_SIZE.clone() as u32, true, 0, 0.1);
+    //The generator and its parameters come from the configuration (or the arguments).
+    println!("World:{:?}",CONFIG.world);
+    let mut g = match CONFIG.world.generator(WORLD_SIZE){
+        Ok(g) => g,
+        Err(e) => {
+            println!("Error creating the world:{}",e);
+            return;
+        }
+    };
 
 
     let mut run = Runner::new(Box::new(r), &mut g).unwrap();
+    *WORLD_SCORE.lock().unwrap()=g.score();
 
+    //In replay mode the recorded plans replace the threads
+    let replay=std::env::args().any(|a| a=="--replay");
+    //Nobody reads the terminal in a measured run, so we don't wait for it
+    let headless=summary_file().is_some() || TICK_BUDGET.is_some();
 
     loop{
         loop {
             let _ = run.game_tick();
+            if over_budget(){
+                finish_run("tick budget");
+                return;
+            }
             if !*WAIT_FOR_ENERGY.lock().unwrap(){
                 break;
             }
         }
+        if replay{
+            continue;
+        }
 
         // We use this mutex as a semaphore
         *RECHARGE.lock().unwrap()=true;
 
+        let planning_start=Instant::now();
 
-        let time=spawn(||{
+        //What the threads use, taken before the ticks of the planning: the same run always plans on the same data
+        let map=Arc::new(ROBOT_MAP.lock().unwrap().clone());
+        let (x,y)=*POSITION.lock().unwrap();
+        let environment=Arc::new(ENVIRONMENT.lock().unwrap().clone());
+        let forecast=Arc::new(STEP_FORECAST.lock().unwrap().clone());
+        let backpack=Arc::new(BACKPACK.lock().unwrap().clone());
+        let teleports=Arc::new(TELEPORTS.lock().unwrap().clone());
+        let planning_tick=STATS.lock().unwrap().ticks as u64;
+
+        let time=spawn(move ||{
             let mut thread_flag=true;
 
             //Return values from the threads:
@@ -991,112 +2154,32 @@ fn main() {
                 //First thread, which will launch the other threads.
                 let mut handlers=vec![];
 
-
-                let data=ROBOT_MAP.lock().unwrap();
-                let map=Arc::new(data.clone());
-
                 // Positions we can choose to go
                 let positions=POSITIONS_TO_GO.lock().unwrap().clone();
 
 
-                let x=POSITION.lock().unwrap().0.clone();
-                let y=POSITION.lock().unwrap().1.clone();
-
-
-                for i in positions{
+                for (k,i) in positions.into_iter().enumerate(){
 
                     //I launch a thread for every specific direction which we may follow
                     let thread_map=Arc::clone(&map);
+                    let thread_environment=Arc::clone(&environment);
+                    let thread_forecast=Arc::clone(&forecast);
+                    let thread_backpack=Arc::clone(&backpack);
+                    let thread_teleports=Arc::clone(&teleports);
 
 
                     //Move converts any variables captured by reference or mutable reference to variables captured by value
                     let handle=spawn( move ||{
-                        //Robot map for the threads.
-                        let inside_thread_map=Arc::clone(&thread_map);
+                        //With a planner seed, the same search gives the same path
+                        seed_planner(&[planning_tick,counter_try as u64,k as u64]);
 
                         //Get position of where our thread's destination is.
                         let (destination_x,destination_y)=get_next_position(i);
 
+                        let dest_x=(x as i32+destination_x).max(0) as usize;
+                        let dest_y=(y as i32+destination_y).max(0) as usize;
 
-                        let mut genetic_set=Vec::new();
-
-                        //Initial population
-                        for _ in 0..POPULATION_NUMBER{
-                            let n=GeneticSearch::new(INPUT_DIR_SIZE,x as i32,y as i32,&inside_thread_map);
-                            genetic_set.push(n);
-                        }
-
-                        // f is a test variable I used to check when it finds his first distanze=0 for a possible position to go
-                        let mut f=true;
-
-                        let mut dest_x= 0;
-
-                        if x as i32 +destination_x>=0{
-                            dest_x= (x as i32+destination_x) as usize;
-                        }
-
-                        let mut dest_y=0;
-
-                        if y as i32+destination_y>=0{
-                            dest_y=(y as i32+destination_y) as usize
-                        }
-
-                        //We repeat the Selection, Crossover and mutation:
-                        for _ in 0..GENERATION_LIMIT{
-                            //I fixed the generation limit to 150, which is optimal, since also the children learn from the parents.
-
-                            //Genetic Fitness, we calculate the weight of the random generated directions
-                            for i in genetic_set.iter_mut(){
-                                i.genetic_cost(&inside_thread_map,(dest_x,dest_y));
-
-                                if f && i.distanze_from_dest==0{ f=false; }
-
-                            }
-
-                            //Genetic Selection: we take an elite set and one based on probability.
-                            //This way, also the children can learn.
-                            //We take the first and second based on the distance from destination and the weight.
-                            let (first,second)=genetic_selection(&mut genetic_set);
-
-                            //Genetic crossover. Here we generate new sons from first and second (two strongest set)
-                            genetic_crossover(&mut genetic_set, &first, &second, &x, &y);
-
-                            //Genetic mutation. Where are going to change a some value for escaping the local min problem.
-                            genetic_mutation(&mut genetic_set);
-
-                            //We also push the two winning parents with their children
-                            // The two strongest set won't have to mutate. If they need, they will do that the next cycle
-                            genetic_set.push(first);
-                            genetic_set.push(second);
-
-                        }
-
-
-                        //We generate the last generation:
-                        for i in genetic_set.iter_mut(){
-                            i.genetic_cost(&inside_thread_map,(dest_x,dest_y));
-                        }
-
-
-                        //we take the fastest sample:
-                        let mut index_res=0;
-                        for i in genetic_set.iter().enumerate(){
-                            if i.1.distanze_from_dest<=genetic_set[index_res].distanze_from_dest{
-                                if i.1.weight<genetic_set[index_res].weight{
-                                    index_res=i.0;
-                                }
-                            }
-                        }
-                        /*
-                        println!("I got weight:{},distance:{}, cost:{} and this series:{:?}",genetic_set[index_res].weight,genetic_set[index_res].distanze_from_dest,genetic_set[index_res].cost,genetic_set[index_res].vector);
-                         */
-
-                        if index_res!=INFINITE{
-                            Some(genetic_set[index_res].clone())
-                        }else{
-                            Option::None
-                        }
-
+                        Some(evolve(&thread_map,(x,y),(dest_x,dest_y),&thread_environment,&thread_forecast,&thread_backpack,&thread_teleports))
                     });
                     handlers.push(handle);
                 }
@@ -1164,19 +2247,29 @@ fn main() {
         //That's an ideal time for the threads to finish their work. It also helps me to keep a balanced energy level for the robot.
         for _ in 0..20{
             let _=run.game_tick();
+            //The budget counts these ticks too (the threads stop with the program)
+            if over_budget(){
+                finish_run("tick budget");
+                return;
+            }
             sleep(Duration::from_millis(10));
         }
 
         //We wait for the thread, which contains all the other threads, to finish
         let t=time.join().unwrap();
+        STATS.lock().unwrap().planning_ms+=planning_start.elapsed().as_millis();
 
         //Check if we got an error in the calculation
         if t.weight>=1000{
+            STATS.lock().unwrap().failures+=1;
+            finish_run("planner failed");
             return;
         }
 
         // Try to give time to read all data information
-        sleep(Duration::from_secs(2));
+        if !headless{
+            sleep(Duration::from_secs(2));
+        }
 
         *RECHARGE.lock().unwrap()=false;
         FOLLOW_DIRECTIONS.lock().unwrap().path_to_follow=t.vector;
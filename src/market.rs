@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use robotics_lib::world::tile::Content;
+
+
+// How much energy we think a coin is worth. We sell only if the coins pay the energy of the detour.
+pub static COIN_VALUE:usize=10;
+
+// How many samples of every price we keep.
+static MAX_SALES:usize=20;
+
+
+// The markets we know and the prices we learn selling things.
+pub struct Markets{
+    // Position -> how many trades the market can still do
+    markets:HashMap<(usize,usize),usize>,
+    // Coins we got for one element, for every sale
+    sales:HashMap<Content,Vec<f32>>,
+    // The sale we are doing now: content, quantity and coins received
+    current_sale:Option<(Content,usize,usize)>,
+}
+
+impl Default for Markets{
+    fn default() -> Self {
+        Markets{
+            markets:HashMap::new(),
+            sales:HashMap::new(),
+            current_sale:Option::None,
+        }
+    }
+}
+
+impl Markets{
+
+    pub(crate) fn observe(&mut self,position:(usize,usize),content:&Content){
+        match content{
+            Content::Market(n) if *n>0 => {self.markets.insert(position,*n);}
+            _ => {self.markets.remove(&position);}
+        }
+    }
+
+    pub(crate) fn is_market(&self,position:(usize,usize))->bool{
+        self.markets.contains_key(&position)
+    }
+
+    pub(crate) fn positions(&self)->impl Iterator<Item=&(usize,usize)>{
+        self.markets.keys()
+    }
+
+    // Position and trades left of every market.
+    pub(crate) fn trades(&self)->impl Iterator<Item=(&(usize,usize),&usize)>{
+        self.markets.iter()
+    }
+
+    pub(crate) fn nearest(&self,from:(usize,usize))->Option<(usize,usize)>{
+        self.markets.keys().min_by_key(|p| p.0.abs_diff(from.0)+p.1.abs_diff(from.1)).cloned()
+    }
+
+    // Coins we expect for one element. Our guess, until we sell it.
+    pub(crate) fn price(&self,content:&Content)->f32{
+        match self.sales.get(&content.to_default()){
+            Some(v) if !v.is_empty() => v.iter().sum::<f32>()/v.len() as f32,
+            _ => match content{
+                Content::Rock(_) => 1.0,
+                Content::Tree(_) => 2.0,
+                Content::Fish(_) => 3.0,
+                _ => 0.0,
+            }
+        }
+    }
+
+    pub(crate) fn prices(&self)->HashMap<Content,f32>{
+        [Content::Rock(0),Content::Tree(0),Content::Fish(0)].into_iter()
+            .map(|c| {let p=self.price(&c); (c,p)})
+            .collect()
+    }
+
+    // The coins are worth more than the energy we spend to go to the market and sell.
+    pub(crate) fn worth_selling(&self,content:&Content,quantity:usize,detour_cost:usize)->bool{
+        let gain=self.price(content)*quantity as f32*COIN_VALUE as f32;
+        gain>(detour_cost+content.properties().cost()*quantity) as f32
+    }
+
+    // We are going to put the content in a market. The coins arrive with the events.
+    pub(crate) fn start_sale(&mut self,content:&Content,quantity:usize){
+        self.current_sale=Some((content.to_default(),quantity,0));
+    }
+
+    pub(crate) fn is_selling(&self)->bool{
+        self.current_sale.is_some()
+    }
+
+    pub(crate) fn coins_received(&mut self,coins:usize){
+        if let Some((_,_,received))=self.current_sale.as_mut(){
+            *received+=coins;
+        }
+    }
+
+    // "sold" is how many elements the market took.
+    pub(crate) fn end_sale(&mut self,sold:usize){
+        if let Some((content,_,coins))=self.current_sale.take(){
+            if sold==0{return}
+
+            let v=self.sales.entry(content).or_insert(Vec::new());
+            v.push(coins as f32/sold as f32);
+            if v.len()>MAX_SALES{
+                v.remove(0);
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use robotics_lib::world::tile::Tile;
+use robotics_lib::world::tile::TileType::{Lava, ShallowWater};
+use crate::helpers_functions::is_good_tile;
+
+
+// Max energy level of the robot.
+pub static MAX_ENERGY:usize=1000;
+
+// Energy we don't use for a segment, because we want to look around (or fix a wrong prediction) once we arrive.
+pub static SEGMENT_RESERVE:usize=150;
+
+
+// A piece of the path we can do with one charge.
+// "start..end" are the indexes of the actions inside the path to follow.
+#[derive(Debug,Clone,PartialEq)]
+pub struct PathSegment{
+    pub(crate) start:usize,
+    pub(crate) end:usize,
+    pub(crate) cost:usize,
+    // Where we stop (and wait for the energy) at the end of the segment.
+    pub(crate) resume_point:(usize,usize),
+    // False if we couldn't find a good place to stop inside the segment.
+    pub(crate) safe:bool,
+}
+
+
+// We don't want to stop in the shallow water, or close to the lava, while we are waiting for the energy.
+pub(crate) fn is_safe_stop(map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize)->bool{
+    if !is_good_tile(&map[x][y]) || map[x][y].as_ref().unwrap().tile_type==ShallowWater{
+        return false;
+    }
+
+    for i in -1..=1{
+        for j in -1..=1{
+            let x1=x as i32+i;
+            let y1=y as i32+j;
+
+            if x1<0 || y1<0 || x1 as usize>=map.len() || y1 as usize>=map.len(){continue}
+
+            match &map[x1 as usize][y1 as usize]{
+                Some(tile) if tile.tile_type==Lava => return false,
+                _ => {}
+            }
+        }
+    }
+    true
+}
+
+
+// We split the path in segments, each one with a cost lower than the budget.
+// "steps" is the cost of every action with the position after it (see EnergyModel::predict_steps).
+// Every segment, if possible, ends in a safe point. If we go over the budget before finding one,
+// we cut at the last action we can pay.
+pub(crate) fn split_in_segments(steps:&Vec<(usize,(usize,usize))>,start_position:(usize,usize),map:&Vec<Vec<Option<Tile>>>,budget:usize)->Vec<PathSegment>{
+    let mut segments=Vec::new();
+    let mut start=0;
+
+    while start<steps.len(){
+        let mut cost=0;
+        let mut end=start;
+        let mut last_safe:Option<(usize,usize)>=Option::None;
+
+        for k in start..steps.len(){
+            //At least one action for every segment, otherwise we never finish
+            if cost+steps[k].0>budget && k>start{break}
+
+            cost+=steps[k].0;
+            end=k+1;
+
+            let (x,y)=steps[k].1;
+            if is_safe_stop(map,x,y){
+                last_safe=Some((end,cost));
+            }
+        }
+
+        let (cut,cut_cost,safe)=if end==steps.len(){
+            (end,cost,true)
+        }else{
+            match last_safe{
+                Some((e,c)) => (e,c,true),
+                Option::None => (end,cost,false),
+            }
+        };
+
+        let resume_point=if cut==0{start_position}else{steps[cut-1].1};
+
+        segments.push(PathSegment{
+            start,
+            end:cut,
+            cost:cut_cost,
+            resume_point,
+            safe,
+        });
+
+        start=cut;
+    }
+
+    segments
+}
+
+
+#[cfg(test)]
+mod tests{
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+    use super::{is_safe_stop, split_in_segments};
+
+    fn tile(tile_type:TileType)->Option<Tile>{
+        Some(Tile{tile_type,content:Content::None,elevation:0})
+    }
+
+    fn grass()->Vec<Vec<Option<Tile>>>{
+        vec![vec![tile(TileType::Grass);5];5]
+    }
+
+    // A straight path on the row 1, every step costs "cost".
+    fn steps(cost:usize,n:usize)->Vec<(usize,(usize,usize))>{
+        (1..=n).map(|y| (cost,(1,y-1))).collect()
+    }
+
+    #[test]
+    fn safe_stops_are_known_walkable_and_far_from_the_lava(){
+        let mut map=grass();
+        map[0][0]=Option::None;
+        map[2][2]=tile(TileType::ShallowWater);
+        map[4][4]=tile(TileType::Lava);
+
+        assert!(is_safe_stop(&map,1,1));
+        assert!(!is_safe_stop(&map,0,0));
+        assert!(!is_safe_stop(&map,2,2));
+        assert!(!is_safe_stop(&map,3,3));
+        assert!(!is_safe_stop(&map,4,4));
+    }
+
+    #[test]
+    fn segments_cover_the_path_within_the_budget(){
+        let map=grass();
+        let steps=steps(100,5);
+        let segments=split_in_segments(&steps,(1,0),&map,250);
+
+        let bounds:Vec<(usize,usize)>=segments.iter().map(|s| (s.start,s.end)).collect();
+        assert_eq!(bounds,vec![(0,2),(2,4),(4,5)]);
+        assert!(segments.iter().all(|s| s.cost<=250 && s.safe));
+        assert_eq!(segments[0].resume_point,(1,1));
+        assert_eq!(segments[2].resume_point,(1,4));
+    }
+
+    #[test]
+    fn segments_stop_at_the_last_safe_point(){
+        let mut map=grass();
+        map[0][3]=tile(TileType::Lava);
+        let steps=steps(100,5);
+
+        //(1,2), (1,3) and (1,4) are close to the lava
+        let segments=split_in_segments(&steps,(1,0),&map,350);
+        assert_eq!((segments[0].start,segments[0].end),(0,2));
+        assert_eq!(segments[0].resume_point,(1,1));
+        assert!(segments[0].safe);
+        assert_eq!(segments.last().unwrap().end,5);
+    }
+
+    #[test]
+    fn without_safe_points_we_cut_at_the_budget(){
+        let mut map=grass();
+        for y in 0..5{
+            map[0][y]=tile(TileType::Lava);
+        }
+        let segments=split_in_segments(&steps(100,5),(1,0),&map,250);
+
+        assert_eq!((segments[0].start,segments[0].end),(0,2));
+        assert!(!segments[0].safe);
+    }
+
+    #[test]
+    fn a_step_over_the_budget_is_a_segment_by_itself(){
+        let segments=split_in_segments(&steps(300,3),(1,0),&grass(),250);
+        assert_eq!(segments.len(),3);
+        assert!(segments.iter().all(|s| s.end==s.start+1));
+    }
+}
@@ -0,0 +1,45 @@
+use std::cell::RefCell;
+use rand::rngs::StdRng;
+use rand::{Error, RngCore, SeedableRng};
+use crate::PLANNER_SEED;
+
+
+// The random numbers of the planners (genetic algorithm and tours), instead of thread_rng.
+// Every thread has its own generator. With a planner seed (see PLANNER_SEED) every search starts from
+// the seed, the tick and the thread, so a recorded run plans the same paths again and the run log
+// can compare them. Without it the searches are random like before.
+thread_local!{
+    static RNG:RefCell<StdRng>=RefCell::new(StdRng::from_entropy());
+}
+
+// We call it at the start of a search, in the thread that does it.
+pub(crate) fn seed_planner(parts:&[u64]){
+    if let Some(seed)=*PLANNER_SEED{
+        let mixed=parts.iter().fold(seed,|s,p| s.wrapping_mul(6364136223846793005).wrapping_add(p.wrapping_add(1442695040888963407)));
+        RNG.with(|rng| *rng.borrow_mut()=StdRng::seed_from_u64(mixed));
+    }
+}
+
+pub(crate) fn planner_rng()->PlannerRng{
+    PlannerRng
+}
+
+pub struct PlannerRng;
+
+impl RngCore for PlannerRng{
+    fn next_u32(&mut self)->u32{
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self)->u64{
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self,dest:&mut [u8]){
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self,dest:&mut [u8])->Result<(),Error>{
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
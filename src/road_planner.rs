@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+use robotics_lib::interface::Direction;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::utils::calculate_cost_go_with_environment;
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use crate::simulator::move_cost;
+
+
+// Set it to false if we never want to pave.
+pub static BUILD_ROADS:bool=true;
+
+// We only pave tiles we walked at least this many times.
+static MIN_TRAVERSALS:usize=3;
+
+static MIN_CORRIDOR_LENGTH:usize=3;
+static MAX_CORRIDOR_LENGTH:usize=10;
+
+// How much energy we think a rock is worth (we could use it for something else).
+static ROCK_VALUE:usize=5;
+
+// Energy used by the tool for every tile, our guess.
+static PAVE_COST_PER_TILE:usize=10;
+
+
+// A straight piece of road we could build, starting next to the robot.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Corridor{
+    pub(crate) direction:Direction,
+    pub(crate) tiles:Vec<(usize,usize)>,
+    // Energy we save every time we walk it
+    pub(crate) saving:usize,
+    // How many times we think we will walk it again
+    pub(crate) traversals:usize,
+}
+
+impl Corridor{
+    pub(crate) fn cost(&self)->usize{
+        self.tiles.len()*(ROCK_VALUE+PAVE_COST_PER_TILE)
+    }
+
+    pub(crate) fn payback(&self)->isize{
+        (self.saving*self.traversals) as isize-self.cost() as isize
+    }
+
+    // The corridor of a recorded run: "length" tiles in that direction, starting next to the robot.
+    pub(crate) fn straight(from:(usize,usize),direction:Direction,length:usize)->Corridor{
+        let (i,j)=match direction{
+            Direction::Up => (-1,0),
+            Direction::Down => (1,0),
+            Direction::Left => (0,-1),
+            Direction::Right => (0,1),
+        };
+        let tiles=(1..=length as isize)
+            .map(|k| (from.0 as isize+i*k,from.1 as isize+j*k))
+            .take_while(|(x,y)| *x>=0 && *y>=0)
+            .map(|(x,y)| (x as usize,y as usize))
+            .collect();
+
+        Corridor{direction,tiles,saving:0,traversals:0}
+    }
+}
+
+
+// It remembers where we walk, and finds the corridors that are worth paving.
+pub struct RoadPlanner{
+    traversals:HashMap<(usize,usize),usize>,
+    paved:HashSet<(usize,usize)>,
+    // Tiles the tool couldn't pave, we don't try them again (they aren't streets, so we don't save them as paved)
+    failed:HashSet<(usize,usize)>,
+}
+
+impl Default for RoadPlanner{
+    fn default() -> Self {
+        RoadPlanner{
+            traversals:HashMap::new(),
+            paved:HashSet::new(),
+            failed:HashSet::new(),
+        }
+    }
+}
+
+impl RoadPlanner{
+
+    pub(crate) fn record(&mut self,position:(usize,usize)){
+        *self.traversals.entry(position).or_insert(0)+=1;
+    }
+
+    pub(crate) fn paved(&mut self,tiles:&[(usize,usize)]){
+        self.paved.extend(tiles.iter().cloned());
+    }
+
+    pub(crate) fn failed(&mut self,tiles:&[(usize,usize)]){
+        self.failed.extend(tiles.iter().cloned());
+    }
+
+    pub(crate) fn traversals(&self)->impl Iterator<Item=(&(usize,usize),&usize)>{
+        self.traversals.iter()
+    }
+
+    pub(crate) fn paved_tiles(&self)->impl Iterator<Item=&(usize,usize)>{
+        self.paved.iter()
+    }
+
+    // The history of a previous run.
+    pub(crate) fn restore(&mut self,position:(usize,usize),traversals:usize){
+        *self.traversals.entry(position).or_insert(0)+=traversals;
+    }
+
+    // The corridor, starting next to us, that pays back the most. We can't pave more tiles than our rocks.
+    pub(crate) fn best_corridor(&self,map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize,rocks:usize,environment:Option<&EnvironmentalConditions>)->Option<Corridor>{
+        let mut best:Option<Corridor>=Option::None;
+
+        for (direction,(i,j)) in [(Direction::Up,(-1,0)),(Direction::Down,(1,0)),(Direction::Left,(0,-1)),(Direction::Right,(0,1))]{
+            let mut tiles=Vec::new();
+            let mut saving=0;
+            let mut traversals=usize::MAX;
+            let mut previous=(x,y);
+
+            for k in 1..=MAX_CORRIDOR_LENGTH.min(rocks){
+                let tx=x as i32+i*k as i32;
+                let ty=y as i32+j*k as i32;
+                if tx<0 || ty<0 || tx as usize>=map.len() || ty as usize>=map.len(){break}
+
+                let position=(tx as usize,ty as usize);
+                let walked=*self.traversals.get(&position).unwrap_or(&0);
+                if walked<MIN_TRAVERSALS || self.paved.contains(&position) || self.failed.contains(&position){break}
+
+                let tile=match &map[position.0][position.1]{
+                    Some(t) => t,
+                    Option::None => break,
+                };
+                if !is_pavable(tile.tile_type) || tile.content!=Content::None{break}
+
+                //What we save is the difference between walking the tile now and walking a street
+                let now=move_cost(map,previous,position,environment).unwrap_or(0);
+                let street=street_cost(map,previous,tile,environment);
+
+                saving+=now.saturating_sub(street);
+                traversals=traversals.min(walked);
+                tiles.push(position);
+                previous=position;
+            }
+
+            if tiles.len()<MIN_CORRIDOR_LENGTH{continue}
+
+            let corridor=Corridor{direction,tiles,saving,traversals};
+            if corridor.payback()>0 && best.as_ref().map_or(true,|b| corridor.payback()>b.payback()){
+                best=Some(corridor);
+            }
+        }
+
+        best
+    }
+}
+
+
+// The tiles where a street makes sense: we can walk them, and they cost more than a street.
+fn is_pavable(tile_type:TileType)->bool{
+    match tile_type{
+        TileType::Hill | TileType::Sand | TileType::Snow | TileType::Grass => true,
+        _ => false,
+    }
+}
+
+// What moving on the tile would cost if it was a street (same elevation).
+fn street_cost(map:&Vec<Vec<Option<Tile>>>,from:(usize,usize),tile:&Tile,environment:Option<&EnvironmentalConditions>)->usize{
+    let mut cost=TileType::Street.properties().cost();
+    if let Some(e)=environment{
+        cost=calculate_cost_go_with_environment(cost,e.clone(),TileType::Street);
+    }
+
+    match &map[from.0][from.1]{
+        Some(current) if tile.elevation>current.elevation => cost+(tile.elevation-current.elevation).pow(2),
+        _ => cost,
+    }
+}
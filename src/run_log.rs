@@ -0,0 +1,355 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use robotics_lib::interface::Direction;
+use robotics_lib::world::tile::Content;
+use crate::genetic_algorithm::{Action, InputDir};
+use crate::sensing_planner::SensorCall;
+use crate::world_file::{number, parse_content_value};
+
+
+// We write on the disk only after this many lines.
+static FLUSH_EVERY:usize=100;
+
+
+// What happened after one action of the robot.
+#[derive(Debug,Clone,PartialEq)]
+pub struct ActionRecord{
+    pub(crate) tick:usize,
+    pub(crate) action:String,
+    pub(crate) result:String,
+    pub(crate) energy:usize,
+    pub(crate) position:(usize,usize),
+}
+
+// The first record that isn't like the recorded one.
+#[derive(Debug,Clone)]
+pub struct Divergence{
+    pub(crate) index:usize,
+    pub(crate) expected:Option<ActionRecord>,
+    pub(crate) actual:ActionRecord,
+}
+
+
+// A call to the world decided by a planner, which isn't an action of a plan.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Call{
+    Sense(SensorCall),
+    // Direction and length of the road
+    Pave(Direction,usize),
+    // Content we search and distance
+    Search(Content,usize),
+}
+
+// What the planners decided, in the order of the run. In replay we take them from the file instead of planning,
+// at the same tick of the recorded run (so the robot recharges the same energy before them).
+#[derive(Debug,Clone,PartialEq)]
+pub enum Decision{
+    Plan(Vec<Action>),
+    Call(Call),
+}
+
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum RunMode{
+    Normal,
+    // We write every plan and every action in the file
+    Record,
+    // We do the decisions of the file (our planners don't run), and the results must be the same
+    Replay,
+    // We plan with our code, and we check the results are the same of the file
+    Compare,
+}
+
+
+// Recording and replay of a run, to find where a new version of the planners does something different.
+// "--record run.log" writes the run, "--replay run.log" does it again with the same plans,
+// "--compare run.log" does it again with the plans of the current code. The world must be the same
+// (a world file, or the same seed).
+//
+// The file has a line for every plan, for every call the planners did to the world (sensors, roads, searches)
+// and for the result of every action and call (the extinguished fires are only results, the path decides them):
+//
+//      plan;3;M:Top M:Top D:Left P:Left:Garbage(0):5
+//      call;3;S:View:Up:5
+//      action;3;S:View:Up:5;Ok;990;10,12
+//      action;4;M:Top;Ok;980;9,12
+//      action;6;X:Left;Ok;950;8,12
+pub struct RunLog{
+    mode:RunMode,
+    tick:usize,
+    writer:Option<BufWriter<File>>,
+    pending:usize,
+    // What we read from the file, with the tick of every decision
+    decisions:VecDeque<(usize,Decision)>,
+    actions:Vec<ActionRecord>,
+    next_action:usize,
+}
+
+impl Default for RunLog{
+    fn default() -> Self {
+        RunLog{
+            mode:RunMode::Normal,
+            tick:0,
+            writer:Option::None,
+            pending:0,
+            decisions:VecDeque::new(),
+            actions:Vec::new(),
+            next_action:0,
+        }
+    }
+}
+
+impl RunLog{
+
+    pub(crate) fn from_args()->RunLog{
+        let args:Vec<String>=std::env::args().collect();
+        let mut log=RunLog::default();
+
+        for (flag,mode) in [("--record",RunMode::Record),("--replay",RunMode::Replay),("--compare",RunMode::Compare)]{
+            let filename=match args.iter().position(|a| a==flag){
+                Some(i) if i+1<args.len() => args[i+1].clone(),
+                _ => continue,
+            };
+
+            let result=if mode==RunMode::Record{
+                File::create(&filename).map(|f| log.writer=Some(BufWriter::new(f))).map_err(|e| e.to_string())
+            }else{
+                log.load(&filename)
+            };
+
+            match result{
+                Ok(_) => {
+                    println!("Run log {:?} with {}",mode,filename);
+                    log.mode=mode;
+                }
+                Err(e) => println!("Error with the run log {}:{}",filename,e),
+            }
+            break;
+        }
+        log
+    }
+
+    pub(crate) fn mode(&self)->&RunMode{
+        &self.mode
+    }
+
+    pub(crate) fn next_tick(&mut self){
+        self.tick+=1;
+    }
+
+    fn load(&mut self,filename:&str)->Result<(),String>{
+        let text=fs::read_to_string(filename).map_err(|e| e.to_string())?;
+
+        for (n,line) in text.lines().enumerate(){
+            let error=|e:String| format!("line {}: {}",n+1,e);
+            let parts:Vec<&str>=line.split(';').collect();
+
+            match parts.as_slice(){
+                ["plan",tick,plan] => {
+                    let plan=plan.split_whitespace().map(parse_action).collect::<Result<Vec<Action>,String>>().map_err(error)?;
+                    self.decisions.push_back((number(Some(tick)).map_err(error)?,Decision::Plan(plan)));
+                }
+                ["call",tick,call] => self.decisions.push_back((number(Some(tick)).map_err(error)?,Decision::Call(parse_call(call).map_err(error)?))),
+                ["action",tick,action,result,energy,position] => {
+                    let (x,y)=position.split_once(',').ok_or(error(String::from("wrong position")))?;
+                    self.actions.push(ActionRecord{
+                        tick:number(Some(tick)).map_err(error)?,
+                        action:action.to_string(),
+                        result:result.to_string(),
+                        energy:number(Some(energy)).map_err(error)?,
+                        position:(number(Some(x)).map_err(error)?,number(Some(y)).map_err(error)?),
+                    });
+                }
+                [""] => {}
+                _ => return Err(error(String::from("unknown line"))),
+            }
+        }
+
+        //Otherwise there is nothing to compare, and we would say that everything is the same
+        if self.actions.is_empty(){
+            return Err(String::from("there are no actions in the run log"));
+        }
+        Ok(())
+    }
+
+    // We have a new plan.
+    pub(crate) fn plan(&mut self,plan:&[Action]){
+        if self.mode==RunMode::Record{
+            let line:Vec<String>=plan.iter().map(action_to_string).collect();
+            self.write(format!("plan;{};{}",self.tick,line.join(" ")));
+        }
+    }
+
+    // A planner is calling the world.
+    pub(crate) fn call(&mut self,call:&Call){
+        if self.mode==RunMode::Record{
+            self.write(format!("call;{};{}",self.tick,call_to_string(call)));
+        }
+    }
+
+    // In replay mode, the next thing the planners decided, if they decided it at this tick (or before).
+    fn next_decision(&mut self,wanted:fn(&Decision)->bool)->Option<Decision>{
+        match self.decisions.front(){
+            Some((tick,decision)) if *tick<=self.tick && wanted(decision) => self.decisions.pop_front().map(|(_,d)| d),
+            _ => Option::None,
+        }
+    }
+
+    // The plan we start now (the plans are written when we start them, see MyRobot::is_energy_right).
+    pub(crate) fn next_plan(&mut self)->Option<Vec<Action>>{
+        match self.next_decision(|d| matches!(d,Decision::Plan(_))){
+            Some(Decision::Plan(plan)) => Some(plan),
+            _ => Option::None,
+        }
+    }
+
+    pub(crate) fn next_call(&mut self)->Option<Call>{
+        match self.next_decision(|d| matches!(d,Decision::Call(_))){
+            Some(Decision::Call(call)) => Some(call),
+            _ => Option::None,
+        }
+    }
+
+    // The sensor calls of the same sensing plan.
+    pub(crate) fn next_sense(&mut self)->Option<SensorCall>{
+        match self.next_decision(|d| matches!(d,Decision::Call(Call::Sense(_)))){
+            Some(Decision::Call(Call::Sense(call))) => Some(call),
+            _ => Option::None,
+        }
+    }
+
+    // There is nothing left to replay.
+    pub(crate) fn decisions_over(&self)->bool{
+        self.decisions.is_empty()
+    }
+
+    // We did an action or a call (see action_to_string and call_to_string). In replay and compare mode we check it against the file.
+    pub(crate) fn action(&mut self,action:String,result:&str,energy:usize,position:(usize,usize))->Option<Divergence>{
+        let actual=ActionRecord{
+            tick:self.tick,
+            action,
+            result:result.to_string(),
+            energy,
+            position,
+        };
+
+        match self.mode{
+            RunMode::Record => {
+                self.write(format!("action;{};{};{};{};{},{}",actual.tick,actual.action,actual.result,actual.energy,position.0,position.1));
+                Option::None
+            }
+            RunMode::Replay | RunMode::Compare => {
+                let index=self.next_action;
+                self.next_action+=1;
+
+                //The tick can change (the threads don't take always the same time), the rest must be equal
+                let expected=self.actions.get(index);
+                let same=expected.map_or(false,|e| e.action==actual.action && e.result==actual.result && e.energy==actual.energy && e.position==actual.position);
+
+                if same{
+                    Option::None
+                }else{
+                    Some(Divergence{index,expected:expected.cloned(),actual})
+                }
+            }
+            RunMode::Normal => Option::None,
+        }
+    }
+
+    // We did all the recorded actions (there is at least one, see load), all with the same results.
+    pub(crate) fn is_finished(&self)->bool{
+        matches!(self.mode,RunMode::Replay | RunMode::Compare) && self.next_action>=self.actions.len()
+    }
+
+    pub(crate) fn report(&self)->String{
+        format!("{} of {} recorded actions compared, all with the same results",self.next_action.min(self.actions.len()),self.actions.len())
+    }
+
+    fn write(&mut self,line:String){
+        if let Some(w)=self.writer.as_mut(){
+            if let Err(e)=writeln!(w,"{}",line){
+                println!("Error writing the run log:{}",e);
+            }
+            self.pending+=1;
+            if self.pending>=FLUSH_EVERY{
+                self.flush();
+            }
+        }
+    }
+
+    pub(crate) fn flush(&mut self){
+        if let Some(w)=self.writer.as_mut(){
+            let _=w.flush();
+        }
+        self.pending=0;
+    }
+}
+
+
+pub(crate) fn call_to_string(call:&Call)->String{
+    match call{
+        Call::Sense(SensorCall::Spotlight(distance)) => format!("S:Spotlight:{}",distance),
+        Call::Sense(SensorCall::OneDirection(dir,distance)) => format!("S:View:{:?}:{}",dir,distance),
+        Call::Pave(dir,length) => format!("R:{:?}:{}",dir,length),
+        Call::Search(content,distance) => format!("F:{:?}:{}",content,distance),
+    }
+}
+
+fn parse_call(text:&str)->Result<Call,String>{
+    let parts:Vec<&str>=text.split(':').collect();
+    match parts.as_slice(){
+        ["S","Spotlight",distance] => Ok(Call::Sense(SensorCall::Spotlight(number(Some(distance))?))),
+        ["S","View",dir,distance] => Ok(Call::Sense(SensorCall::OneDirection(parse_direction(dir)?,number(Some(distance))?))),
+        ["R",dir,length] => Ok(Call::Pave(parse_direction(dir)?,number(Some(length))?)),
+        ["F",content,distance] => Ok(Call::Search(parse_content_value(content)?,number(Some(distance))?)),
+        _ => Err(format!("unknown call \"{}\"",text)),
+    }
+}
+
+fn parse_direction(text:&str)->Result<Direction,String>{
+    match text{
+        "Up" => Ok(Direction::Up),
+        "Down" => Ok(Direction::Down),
+        "Left" => Ok(Direction::Left),
+        "Right" => Ok(Direction::Right),
+        other => Err(format!("unknown direction \"{}\"",other)),
+    }
+}
+
+pub(crate) fn action_to_string(action:&Action)->String{
+    match action{
+        Action::Move(dir) => format!("M:{:?}",dir),
+        Action::Destroy(dir) => format!("D:{:?}",dir),
+        Action::Put(dir,content,quantity) => format!("P:{:?}:{:?}:{}",dir,content,quantity),
+        Action::Wait => String::from("W"),
+        Action::Sense => String::from("S"),
+        Action::Teleport((x,y)) => format!("T:{},{}",x,y),
+    }
+}
+
+fn parse_action(text:&str)->Result<Action,String>{
+    let parts:Vec<&str>=text.split(':').collect();
+    match parts.as_slice(){
+        ["M",dir] => Ok(Action::Move(parse_dir(dir)?)),
+        ["D",dir] => Ok(Action::Destroy(parse_dir(dir)?)),
+        ["P",dir,content,quantity] => Ok(Action::Put(parse_dir(dir)?,parse_content_value(content)?,number(Some(quantity))?)),
+        ["W"] => Ok(Action::Wait),
+        ["S"] => Ok(Action::Sense),
+        ["T",position] => {
+            let (x,y)=position.split_once(',').ok_or(format!("wrong teleport \"{}\"",text))?;
+            Ok(Action::Teleport((number(Some(x))?,number(Some(y))?)))
+        }
+        _ => Err(format!("unknown action \"{}\"",text)),
+    }
+}
+
+fn parse_dir(text:&str)->Result<InputDir,String>{
+    match text{
+        "Right" => Ok(InputDir::Right),
+        "Left" => Ok(InputDir::Left),
+        "Top" => Ok(InputDir::Top),
+        "Bottom" => Ok(InputDir::Bottom),
+        other => Err(format!("unknown direction \"{}\"",other)),
+    }
+}
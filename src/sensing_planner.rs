@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use robotics_lib::interface::Direction;
+use robotics_lib::world::tile::Tile;
+use crate::energy_model::EnergyModel;
+
+
+// Energy we always keep for moving after we have looked around.
+pub static SENSING_RESERVE:usize=100;
+
+
+// A single call to one of our "sensors".
+#[derive(Debug,Clone,PartialEq)]
+pub enum SensorCall{
+    Spotlight(usize),
+    OneDirection(Direction,usize),
+}
+
+impl SensorCall{
+
+    // Tiles (in bounds) that this call should uncover, starting from (x,y).
+    // They are estimations: the spotlight is taken as a square and the one_direction_view as a 3 wide strip.
+    pub(crate) fn footprint(&self,x:usize,y:usize,len:usize)->Vec<(usize,usize)>{
+        let mut v=Vec::new();
+        let x=x as i32;
+        let y=y as i32;
+
+        let mut push=|i:i32,j:i32|{
+            if i>=0 && j>=0 && (i as usize)<len && (j as usize)<len{
+                v.push((i as usize,j as usize));
+            }
+        };
+
+        match self{
+            SensorCall::Spotlight(d)=>{
+                let d=*d as i32;
+                for i in -d..=d{
+                    for j in -d..=d{
+                        push(x+i,y+j);
+                    }
+                }
+            }
+            SensorCall::OneDirection(dir,d)=>{
+                let (di,dj)=match dir{
+                    Direction::Up=>(-1,0),
+                    Direction::Down=>(1,0),
+                    Direction::Left=>(0,-1),
+                    Direction::Right=>(0,1),
+                };
+                for step in 1..=*d as i32{
+                    for side in -1..=1{
+                        if di==0{
+                            push(x+side,y+dj*step);
+                        }else{
+                            push(x+di*step,y+side);
+                        }
+                    }
+                }
+            }
+        }
+        v
+    }
+}
+
+
+// A possible call, with how many new tiles we think it shows and how much it costs.
+#[derive(Debug,Clone)]
+pub struct SensingOption{
+    pub(crate) call:SensorCall,
+    pub(crate) cost:usize,
+    pub(crate) gain:usize,
+}
+
+
+pub struct SensingPlanner{
+    options:Vec<SensingOption>,
+}
+
+impl SensingPlanner{
+
+    // The spotlight cost must be asked to the tool, so it's given from outside.
+    // The energy model corrects it with what we have really spent the other times.
+    pub(crate) fn new(spotlight_distance:usize,spotlight_cost:usize,one_direction_distance:usize,model:&EnergyModel)->Self{
+        let spotlight=SensorCall::Spotlight(spotlight_distance);
+        let mut options=vec![SensingOption{
+            cost:model.predict_sense(&spotlight,spotlight_cost),
+            call:spotlight,
+            gain:0,
+        }];
+
+        for dir in [Direction::Right,Direction::Down,Direction::Left,Direction::Up]{
+            let call=SensorCall::OneDirection(dir,one_direction_distance);
+            options.push(SensingOption{
+                cost:model.predict_sense(&call,0),
+                call,
+                gain:0,
+            });
+        }
+
+        SensingPlanner{options}
+    }
+
+    // We choose the calls based on the new tiles they show compared to their cost.
+    // Every time we take one, the tiles it shows are not counted anymore for the others,
+    // so we don't pay twice to see the same area.
+    pub(crate) fn plan(&mut self,map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize,budget:usize)->Vec<SensingOption>{
+        let len=map.len();
+        let mut seen:HashSet<(usize,usize)>=HashSet::new();
+        let mut result=Vec::new();
+        let mut spent=0;
+
+        let mut remaining=self.options.clone();
+
+        loop{
+            let mut best:Option<usize>=Option::None;
+            let mut best_ratio=0.0;
+
+            for (index,option) in remaining.iter_mut().enumerate(){
+                option.gain=option.call.footprint(x,y,len).iter()
+                    .filter(|(i,j)| map[*i][*j].is_none() && !seen.contains(&(*i,*j)))
+                    .count();
+
+                if option.gain==0 || spent+option.cost>budget{continue}
+
+                let ratio=option.gain as f32/option.cost.max(1) as f32;
+                if ratio>best_ratio{
+                    best_ratio=ratio;
+                    best=Some(index);
+                }
+            }
+
+            match best{
+                Some(index)=>{
+                    let option=remaining.remove(index);
+                    for t in option.call.footprint(x,y,len){
+                        seen.insert(t);
+                    }
+                    spent+=option.cost;
+                    result.push(option);
+                }
+                Option::None=>break,
+            }
+        }
+
+        result
+    }
+
+    pub(crate) fn total_cost(plan:&Vec<SensingOption>)->usize{
+        plan.iter().map(|o| o.cost).sum()
+    }
+}
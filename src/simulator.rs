@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+use robotics_lib::utils::calculate_cost_go_with_environment;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use crate::genetic_algorithm::Action;
+use crate::helpers_functions::{is_good_tile, respective_container};
+use crate::weather_forecast::StepForecast;
+use crate::content_handling::{content_use, ContentUse};
+use crate::{CONFIG, ONE_DIRECTION_DISTANCE};
+use crate::energy_model::VIEW_COST_PER_TILE;
+
+
+// Our guess of the energy used by a teleport, the energy model corrects it with what we observe.
+pub static TELEPORT_COST:usize=30;
+
+// What happened with a single action of the genome.
+#[derive(Debug,Clone,PartialEq)]
+pub struct StepTrace{
+    pub(crate) action:Action,
+    // Position of the robot after the action
+    pub(crate) position:(usize,usize),
+    pub(crate) cost:usize,
+    pub(crate) collected:Option<Content>,
+    pub(crate) deposited:Option<(Content,usize)>,
+    // We put the water on a fire
+    pub(crate) extinguished:bool,
+    // The action couldn't be done (unknown tile, lava, nothing to destroy...). The robot didn't move.
+    pub(crate) blocked:bool,
+}
+
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Trace{
+    pub(crate) steps:Vec<StepTrace>,
+}
+
+impl Trace{
+    pub(crate) fn total_cost(&self)->usize{
+        self.steps.iter().map(|s| s.cost).sum()
+    }
+}
+
+
+// State of the robot during the simulation
+pub struct SimState{
+    pub(crate) position:(usize,usize),
+    pub(crate) spent:usize,
+    pub(crate) waited:usize,
+    // Tiles we have already destroyed, we can't collect them two times.
+    destroyed:HashSet<(usize,usize)>,
+}
+
+
+// It replays a genome from a position, on a snapshot of the map.
+// It's the only place where we decide if a move can be done and how much it costs,
+// so the genetic algorithm, the energy model and the weather forecast agree.
+pub struct Simulator<'a>{
+    map:&'a Vec<Vec<Option<Tile>>>,
+    environment:Option<&'a EnvironmentalConditions>,
+    forecast:Option<&'a StepForecast>,
+    // What we have in the backpack, it changes what we want to collect
+    backpack:&'a [(Content,usize)],
+    sense_cost:usize,
+}
+
+impl<'a> Simulator<'a>{
+
+    pub(crate) fn new(map:&'a Vec<Vec<Option<Tile>>>)->Self{
+        Simulator{
+            map,
+            environment:Option::None,
+            forecast:Option::None,
+            backpack:&[],
+            sense_cost:ONE_DIRECTION_DISTANCE*VIEW_COST_PER_TILE,
+        }
+    }
+
+    // Conditions used for all the steps (if we don't have a forecast for them).
+    pub(crate) fn with_environment(mut self,environment:Option<&'a EnvironmentalConditions>)->Self{
+        self.environment=environment;
+        self
+    }
+
+    // What the energy model predicts for the sense of the path.
+    pub(crate) fn with_sense_cost(mut self,sense_cost:usize)->Self{
+        self.sense_cost=sense_cost;
+        self
+    }
+
+    pub(crate) fn with_forecast(mut self,forecast:&'a StepForecast)->Self{
+        self.forecast=Some(forecast);
+        self
+    }
+
+    pub(crate) fn with_backpack(mut self,backpack:&'a [(Content,usize)])->Self{
+        self.backpack=backpack;
+        self
+    }
+
+    pub(crate) fn start(&self,position:(usize,usize),waited:usize)->SimState{
+        SimState{
+            position,
+            spent:0,
+            waited,
+            destroyed:HashSet::new(),
+        }
+    }
+
+    pub(crate) fn inside(&self,x:i32,y:i32)->bool{
+        x>=0 && y>=0 && (x as usize)<self.map.len() && (y as usize)<self.map[x as usize].len()
+    }
+
+    pub(crate) fn is_known(&self,x:i32,y:i32)->bool{
+        self.inside(x,y) && self.map[x as usize][y as usize].is_some()
+    }
+
+    // We never walk in the fire, we have to extinguish it before.
+    pub(crate) fn can_enter(&self,x:i32,y:i32)->bool{
+        self.is_known(x,y) && is_good_tile(&self.map[x as usize][y as usize]) && self.map[x as usize][y as usize].as_ref().unwrap().content!=Content::Fire
+    }
+
+    // We can teleport only from a teleport tile to another one we have already activated.
+    pub(crate) fn on_teleport(&self,position:(usize,usize))->bool{
+        match &self.map[position.0][position.1]{
+            Some(tile) => matches!(tile.tile_type,TileType::Teleport(_)),
+            Option::None => false,
+        }
+    }
+
+    pub(crate) fn can_teleport(&self,from:(usize,usize),to:(usize,usize))->bool{
+        if from==to || !self.on_teleport(from) || !self.is_known(to.0 as i32,to.1 as i32){return false}
+        self.map[to.0][to.1].as_ref().unwrap().tile_type==TileType::Teleport(true)
+    }
+
+    fn environment_for(&self,state:&SimState)->Option<&EnvironmentalConditions>{
+        match self.forecast.and_then(|f| f.after_energy(state.spent,state.waited)){
+            Some(e) => Some(e),
+            Option::None => self.environment,
+        }
+    }
+
+    pub(crate) fn step(&self,state:&mut SimState,action:&Action)->StepTrace{
+        let mut trace=StepTrace{
+            action:action.clone(),
+            position:state.position,
+            cost:0,
+            collected:Option::None,
+            deposited:Option::None,
+            extinguished:false,
+            blocked:false,
+        };
+
+        let (i,j)=action.target();
+        let x=state.position.0 as i32+i;
+        let y=state.position.1 as i32+j;
+
+        match action{
+            Action::Wait => {
+                state.waited+=1;
+            }
+            Action::Sense => {
+                trace.cost=self.sense_cost;
+            }
+            Action::Move(_) => {
+                if self.can_enter(x,y){
+                    let target=(x as usize,y as usize);
+                    trace.cost=move_cost(self.map,state.position,target,self.environment_for(state)).unwrap_or(0);
+                    trace.position=target;
+                }else{
+                    trace.blocked=true;
+                }
+            }
+            Action::Teleport(destination) => {
+                if self.can_teleport(state.position,*destination){
+                    trace.cost=TELEPORT_COST;
+                    trace.position=*destination;
+                }else{
+                    trace.blocked=true;
+                }
+            }
+            Action::Destroy(_) => {
+                if !self.is_known(x,y) || state.destroyed.contains(&(x as usize,y as usize)){
+                    trace.blocked=true;
+                }else{
+                    let content=&self.map[x as usize][y as usize].as_ref().unwrap().content;
+                    if content.to_default()==Content::None{
+                        trace.blocked=true;
+                    }else{
+                        trace.cost=content.properties().cost();
+                        if content_use(content,self.backpack)==ContentUse::Collect{
+                            trace.collected=Some(content.clone());
+                        }
+                        state.destroyed.insert((x as usize,y as usize));
+                    }
+                }
+            }
+            Action::Put(_,content,quantity) => {
+                //We put the content in its container, or we sell it in a market
+                let accepted=self.is_known(x,y) && {
+                    let target=self.map[x as usize][y as usize].as_ref().unwrap().content.to_default();
+                    target==respective_container(content).to_default() || (target==Content::Market(0) && CONFIG.policy.sells(content))
+                };
+
+                let fire=self.is_known(x,y) && self.map[x as usize][y as usize].as_ref().unwrap().content==Content::Fire;
+
+                if fire && content.to_default()==Content::Water(0){
+                    trace.cost=content.properties().cost();
+                    trace.extinguished=true;
+                }else if accepted{
+                    trace.cost=content.properties().cost()*quantity;
+                    trace.deposited=Some((content.clone(),*quantity));
+                }else{
+                    trace.blocked=true;
+                }
+            }
+        }
+
+        state.position=trace.position;
+        state.spent+=trace.cost;
+        trace
+    }
+
+    pub(crate) fn replay(&self,genome:&[Action],start:(usize,usize),waited:usize)->Trace{
+        let mut state=self.start(start,waited);
+        let steps=genome.iter().map(|a| self.step(&mut state,a)).collect();
+
+        Trace{
+            steps,
+        }
+    }
+}
+
+
+// Cost of a move with the same rules of the library: tile cost with the weather, plus the elevation if we go up.
+// Without the conditions we only use the cost of the tile.
+pub(crate) fn move_cost(map:&Vec<Vec<Option<Tile>>>,from:(usize,usize),to:(usize,usize),environment:Option<&EnvironmentalConditions>)->Option<usize>{
+    let target=map[to.0][to.1].as_ref()?;
+    let current=map[from.0][from.1].as_ref()?;
+
+    let mut cost=target.tile_type.properties().cost();
+    if let Some(e)=environment{
+        cost=calculate_cost_go_with_environment(cost,e.clone(),target.tile_type);
+    }
+
+    // Consider elevation cost only if we are going from a lower tile to a higher tile
+    if target.elevation>current.elevation{
+        cost+=(target.elevation-current.elevation).pow(2);
+    }
+
+    Some(cost)
+}
+
+
+#[cfg(test)]
+mod tests{
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+    use crate::genetic_algorithm::{Action, InputDir};
+    use super::{move_cost, Simulator};
+
+    fn tile(tile_type:TileType,content:Content,elevation:usize)->Option<Tile>{
+        Some(Tile{tile_type,content,elevation})
+    }
+
+    // 3x3 of grass, the robot starts in the middle.
+    fn grass()->Vec<Vec<Option<Tile>>>{
+        vec![vec![tile(TileType::Grass,Content::None,0);3];3]
+    }
+
+    #[test]
+    fn move_is_blocked_on_unknown_lava_and_fire(){
+        let mut map=grass();
+        map[1][2]=Option::None;
+        map[0][1]=tile(TileType::Lava,Content::None,0);
+        map[1][0]=tile(TileType::Grass,Content::Fire,0);
+        let simulator=Simulator::new(&map);
+
+        for dir in [InputDir::Right,InputDir::Top,InputDir::Left]{
+            let trace=simulator.replay(&[Action::Move(dir)],(1,1),0);
+            assert!(trace.steps[0].blocked,"{:?}",trace.steps[0].action);
+            assert_eq!(trace.steps[0].position,(1,1));
+            assert_eq!(trace.total_cost(),0);
+        }
+
+        let trace=simulator.replay(&[Action::Move(InputDir::Bottom)],(1,1),0);
+        assert!(!trace.steps[0].blocked);
+        assert_eq!(trace.steps[0].position,(2,1));
+        assert_eq!(trace.total_cost(),TileType::Grass.properties().cost());
+    }
+
+    #[test]
+    fn move_cost_adds_the_elevation_only_going_up(){
+        let mut map=grass();
+        map[1][2]=tile(TileType::Grass,Content::None,3);
+        let grass_cost=TileType::Grass.properties().cost();
+
+        assert_eq!(move_cost(&map,(1,1),(1,2),Option::None),Some(grass_cost+9));
+        assert_eq!(move_cost(&map,(1,2),(1,1),Option::None),Some(grass_cost));
+
+        let trace=Simulator::new(&map).replay(&[Action::Move(InputDir::Right),Action::Move(InputDir::Left)],(1,1),0);
+        assert_eq!(trace.steps[0].cost,grass_cost+9);
+        assert_eq!(trace.steps[1].cost,grass_cost);
+    }
+
+    #[test]
+    fn destroy_twice_the_same_tile_is_blocked(){
+        let mut map=grass();
+        map[1][2]=tile(TileType::Grass,Content::Garbage(2),0);
+        let simulator=Simulator::new(&map);
+
+        let trace=simulator.replay(&[Action::Destroy(InputDir::Right),Action::Destroy(InputDir::Right),Action::Destroy(InputDir::Left)],(1,1),0);
+        assert!(!trace.steps[0].blocked);
+        assert_eq!(trace.steps[0].cost,Content::Garbage(2).properties().cost());
+        assert_eq!(trace.steps[0].collected,Some(Content::Garbage(2)));
+        assert!(trace.steps[1].blocked);
+        //Nothing to destroy
+        assert!(trace.steps[2].blocked);
+    }
+
+    #[test]
+    fn put_in_the_right_container_or_in_a_market(){
+        let mut map=grass();
+        map[1][2]=tile(TileType::Grass,Content::Bin(0..5),0);
+        map[1][0]=tile(TileType::Grass,Content::Market(2),0);
+        map[0][1]=tile(TileType::Grass,Content::Crate(0..5),0);
+        let simulator=Simulator::new(&map);
+
+        let trace=simulator.replay(&[
+            Action::Put(InputDir::Right,Content::Garbage(0),2),
+            Action::Put(InputDir::Left,Content::Fish(0),1),
+            Action::Put(InputDir::Top,Content::Garbage(0),1),
+            Action::Put(InputDir::Bottom,Content::Garbage(0),1),
+        ],(1,1),0);
+
+        assert_eq!(trace.steps[0].deposited,Some((Content::Garbage(0),2)));
+        assert_eq!(trace.steps[0].cost,Content::Garbage(0).properties().cost()*2);
+        assert_eq!(trace.steps[1].deposited,Some((Content::Fish(0),1)));
+        //The crate is for the trees, and there is nothing under us
+        assert!(trace.steps[2].blocked);
+        assert!(trace.steps[3].blocked);
+    }
+
+    #[test]
+    fn teleport_only_to_an_activated_teleport(){
+        let mut map=grass();
+        map[1][1]=tile(TileType::Teleport(true),Content::None,0);
+        map[0][0]=tile(TileType::Teleport(false),Content::None,0);
+        map[2][2]=tile(TileType::Teleport(true),Content::None,0);
+        let simulator=Simulator::new(&map);
+
+        assert!(simulator.can_teleport((1,1),(2,2)));
+        assert!(!simulator.can_teleport((1,1),(0,0)));
+        assert!(!simulator.can_teleport((1,1),(1,1)));
+        //We have to be on a teleport
+        assert!(!simulator.can_teleport((0,1),(2,2)));
+
+        let trace=simulator.replay(&[Action::Teleport((0,0)),Action::Teleport((2,2))],(1,1),0);
+        assert!(trace.steps[0].blocked);
+        assert_eq!(trace.steps[1].position,(2,2));
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use robotics_lib::world::tile::Tile;
+use crate::helpers_functions::is_good_tile;
+
+
+// How many planning rounds we remember.
+static HISTORY_SIZE:usize=20;
+
+// Two positions closer than this are considered the same place.
+static LOOP_RADIUS:usize=3;
+
+// If we come back to the same place this many times in the history, we are oscillating.
+static LOOP_REPETITIONS:usize=3;
+
+// Rounds without new tiles discovered before we say we aren't making progress.
+static NO_PROGRESS_ROUNDS:usize=6;
+
+// Rounds in which we don't want to go back in the places of the loop.
+static FORBID_ROUNDS:usize=10;
+
+// We don't search for frontiers too far, otherwise the path finder takes forever.
+pub static ESCAPE_MAX_DISTANCE:usize=60;
+
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum StuckReason{
+    Loop,
+    NoProgress,
+}
+
+
+pub struct StuckDetector{
+    positions:VecDeque<(usize,usize)>,
+    known_tiles:VecDeque<usize>,
+    // Areas we can't choose as destination, with the rounds still left.
+    forbidden:Vec<((usize,usize),usize)>,
+}
+
+impl Default for StuckDetector{
+    fn default() -> Self {
+        StuckDetector{
+            positions:VecDeque::new(),
+            known_tiles:VecDeque::new(),
+            forbidden:Vec::new(),
+        }
+    }
+}
+
+impl StuckDetector{
+
+    // We call it once for every planning round, with our position and how many tiles we know.
+    // If we haven't moved since the last round (for example we are waiting for the energy) it isn't a new round.
+    pub(crate) fn new_round(&mut self,position:(usize,usize),known_tiles:usize)->Option<StuckReason>{
+        if self.positions.back()==Some(&position){
+            return Option::None;
+        }
+
+        //We compare the position with the history before it
+        let looping=self.is_looping(position);
+
+        self.positions.push_back(position);
+        self.known_tiles.push_back(known_tiles);
+
+        if self.positions.len()>HISTORY_SIZE{
+            self.positions.pop_front();
+            self.known_tiles.pop_front();
+        }
+
+        for f in self.forbidden.iter_mut(){
+            f.1-=1;
+        }
+        self.forbidden.retain(|f| f.1>0);
+
+        if looping{
+            return Some(StuckReason::Loop);
+        }
+
+        if self.no_progress(){
+            return Some(StuckReason::NoProgress);
+        }
+
+        Option::None
+    }
+
+    // We have already been close to this position LOOP_REPETITIONS times.
+    fn is_looping(&self,position:(usize,usize))->bool{
+        let repetitions=self.positions.iter().filter(|p| distance(**p,position)<=LOOP_RADIUS).count();
+        repetitions>=LOOP_REPETITIONS
+    }
+
+    fn no_progress(&self)->bool{
+        if self.known_tiles.len()<=NO_PROGRESS_ROUNDS{return false}
+
+        let last=self.known_tiles[self.known_tiles.len()-1];
+        let before=self.known_tiles[self.known_tiles.len()-1-NO_PROGRESS_ROUNDS];
+
+        last<=before
+    }
+
+    // We forbid all the places of our history, and we start again from zero.
+    // This way the escape has the time to work before we check again.
+    pub(crate) fn escape(&mut self){
+        for p in self.positions.iter(){
+            if !self.forbidden.iter().any(|f| f.0==*p){
+                self.forbidden.push((*p,FORBID_ROUNDS));
+            }
+        }
+        self.positions.clear();
+        self.known_tiles.clear();
+    }
+
+    pub(crate) fn is_forbidden(&self,x:usize,y:usize)->bool{
+        self.forbidden.iter().any(|f| distance(f.0,(x,y))<=LOOP_RADIUS)
+    }
+
+    // The known tile we can walk on, close to an unknown one, which is the farthest from the places we have been.
+    pub(crate) fn farthest_frontier(&self,map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize)->Option<(usize,usize)>{
+        let len=map.len();
+        let from_x=x.saturating_sub(ESCAPE_MAX_DISTANCE);
+        let to_x=(x+ESCAPE_MAX_DISTANCE).min(len-1);
+        let from_y=y.saturating_sub(ESCAPE_MAX_DISTANCE);
+        let to_y=(y+ESCAPE_MAX_DISTANCE).min(len-1);
+
+        let mut best:Option<(usize,usize)>=Option::None;
+        let mut best_distance=0;
+
+        for i in from_x..=to_x{
+            for j in from_y..=to_y{
+                if !is_good_tile(&map[i][j]) || !is_frontier(map,i,j) || self.is_forbidden(i,j){continue}
+
+                let d=self.forbidden.iter().map(|f| distance(f.0,(i,j))).min().unwrap_or(distance((x,y),(i,j)));
+
+                if d>best_distance{
+                    best_distance=d;
+                    best=Some((i,j));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+
+fn distance(a:(usize,usize),b:(usize,usize))->usize{
+    a.0.abs_diff(b.0)+a.1.abs_diff(b.1)
+}
+
+pub(crate) fn is_frontier(map:&Vec<Vec<Option<Tile>>>,x:usize,y:usize)->bool{
+    for (i,j) in [(-1,0),(1,0),(0,-1),(0,1)]{
+        let x1=x as i32+i;
+        let y1=y as i32+j;
+        if x1<0 || y1<0 || x1 as usize>=map.len() || y1 as usize>=map.len(){continue}
+
+        if map[x1 as usize][y1 as usize].is_none(){
+            return true;
+        }
+    }
+    false
+}
+
+pub(crate) fn known_tiles(map:&Vec<Vec<Option<Tile>>>)->usize{
+    map.iter().map(|row| row.iter().filter(|t| t.is_some()).count()).sum()
+}
+
+
+#[cfg(test)]
+mod tests{
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+    use super::{StuckDetector, StuckReason, FORBID_ROUNDS, NO_PROGRESS_ROUNDS};
+
+    #[test]
+    fn waiting_in_the_same_place_isnt_a_new_round(){
+        let mut detector=StuckDetector::default();
+        for _ in 0..10{
+            assert_eq!(detector.new_round((5,5),1),Option::None);
+        }
+        assert_eq!(detector.positions.len(),1);
+    }
+
+    #[test]
+    fn going_back_and_forth_is_a_loop(){
+        let mut detector=StuckDetector::default();
+        let places=[(10,10),(20,20)];
+
+        //The fourth time we come back to the first place
+        for k in 0..6{
+            assert_eq!(detector.new_round(places[k%2],k*10),Option::None,"round {}",k);
+        }
+        assert_eq!(detector.new_round(places[0],60),Some(StuckReason::Loop));
+    }
+
+    #[test]
+    fn no_new_tiles_is_no_progress(){
+        let mut detector=StuckDetector::default();
+        for k in 0..NO_PROGRESS_ROUNDS{
+            assert_eq!(detector.new_round((k*10,0),100),Option::None);
+        }
+        assert_eq!(detector.new_round((NO_PROGRESS_ROUNDS*10,0),100),Some(StuckReason::NoProgress));
+    }
+
+    #[test]
+    fn escaped_places_are_forbidden_for_some_rounds(){
+        let mut detector=StuckDetector::default();
+        detector.new_round((0,0),0);
+        detector.escape();
+
+        assert!(detector.is_forbidden(1,1));
+        assert!(!detector.is_forbidden(50,50));
+
+        for k in 0..FORBID_ROUNDS{
+            detector.new_round((100+k*10,100),k*10);
+        }
+        assert!(!detector.is_forbidden(1,1));
+    }
+
+    #[test]
+    fn the_frontier_is_far_from_where_we_have_been(){
+        //Everything is known except the last column
+        let mut map=vec![vec![Some(Tile{tile_type:TileType::Grass,content:Content::None,elevation:0});5];5];
+        for row in map.iter_mut(){
+            row[4]=Option::None;
+        }
+
+        let detector=StuckDetector::default();
+        assert_eq!(detector.farthest_frontier(&map,2,0),Some((0,3)));
+    }
+}
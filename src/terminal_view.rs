@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use crate::genetic_algorithm::Action;
+use crate::helpers_functions::direction_value;
+
+
+// Half side of the square we draw around the robot.
+static VIEW_RADIUS:usize=20;
+
+
+// What we write under the map.
+pub struct ViewState{
+    pub(crate) position:(usize,usize),
+    pub(crate) energy:usize,
+    pub(crate) backpack:Vec<(Content,usize)>,
+    pub(crate) status:String,
+}
+
+
+// The map around the robot drawn with characters, for the runs we watch from a terminal.
+// We enable it with "--ascii". We write the characters ourselves, the renderer doesn't need other crates.
+pub struct TerminalView{
+    enabled:bool,
+}
+
+impl Default for TerminalView{
+    fn default() -> Self {
+        let enabled=std::env::args().any(|a| a=="--ascii");
+        TerminalView{enabled}
+    }
+}
+
+impl TerminalView{
+
+    pub(crate) fn is_enabled(&self)->bool{
+        self.enabled
+    }
+
+    // We clear the terminal and we draw everything again.
+    pub(crate) fn draw(&self,map:&Vec<Vec<Option<Tile>>>,path:&[Action],containers:&HashSet<(usize,usize)>,state:&ViewState){
+        if !self.enabled || map.is_empty(){return}
+
+        let (x,y)=state.position;
+        let path=path_positions(path,x,y,map.len());
+
+        let mut s=String::from("\x1B[2J\x1B[H");
+        for i in x.saturating_sub(VIEW_RADIUS)..(x+VIEW_RADIUS+1).min(map.len()){
+            for j in y.saturating_sub(VIEW_RADIUS)..(y+VIEW_RADIUS+1).min(map.len()){
+                let c=if (i,j)==(x,y){
+                    '@'
+                }else if containers.contains(&(i,j)){
+                    match &map[i][j]{
+                        Some(t) => content_char(&t.content),
+                        Option::None => '?',
+                    }
+                }else if path.contains(&(i,j)){
+                    '*'
+                }else{
+                    match &map[i][j]{
+                        Some(t) => tile_char(t),
+                        Option::None => ' ',
+                    }
+                };
+                s.push(c);
+            }
+            s.push('\n');
+        }
+
+        s.push_str(&format!("Position:{:?}  Energy:{}  {}\n",state.position,state.energy,state.status));
+        s.push_str("Backpack:");
+        for (content,quantity) in state.backpack.iter(){
+            s.push_str(&format!(" {}x{}",content_char(content),quantity));
+        }
+        println!("{}",s);
+    }
+}
+
+
+// The tiles the path goes through, starting from (x,y).
+fn path_positions(path:&[Action],x:usize,y:usize,len:usize)->HashSet<(usize,usize)>{
+    let mut positions=HashSet::new();
+    let (mut x,mut y)=(x as i32,y as i32);
+
+    for action in path{
+        match action{
+            Action::Move(dir) => {
+                let (i,j)=direction_value(dir);
+                x+=i;
+                y+=j;
+            }
+            Action::Teleport((tx,ty)) => {
+                x=*tx as i32;
+                y=*ty as i32;
+            }
+            _ => continue,
+        }
+        if x<0 || y<0 || x as usize>=len || y as usize>=len{break}
+        positions.insert((x as usize,y as usize));
+    }
+    positions
+}
+
+// The content if there is one, otherwise the tile type.
+fn tile_char(tile:&Tile)->char{
+    if tile.content!=Content::None{
+        return content_char(&tile.content);
+    }
+
+    match tile.tile_type{
+        TileType::DeepWater => '~',
+        TileType::ShallowWater => '-',
+        TileType::Sand => ':',
+        TileType::Grass => '.',
+        TileType::Street => '=',
+        TileType::Hill => 'n',
+        TileType::Mountain => '^',
+        TileType::Snow => '#',
+        TileType::Lava => '&',
+        TileType::Teleport(_) => 'O',
+        _ => 'W',
+    }
+}
+
+fn content_char(content:&Content)->char{
+    match content{
+        Content::Rock(_) => 'r',
+        Content::Tree(_) => 't',
+        Content::Garbage(_) => 'g',
+        Content::Fire => 'f',
+        Content::Coin(_) => 'c',
+        Content::Bin(_) => 'B',
+        Content::Crate(_) => 'C',
+        Content::Bank(_) => '$',
+        Content::Water(_) => 'w',
+        Content::Market(_) => 'M',
+        Content::Fish(_) => 'p',
+        Content::Bush(_) => 'b',
+        Content::None => ' ',
+        _ => 'H',
+    }
+}
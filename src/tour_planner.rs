@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use rand::Rng;
+use crate::planner_rng::planner_rng;
+use robotics_lib::world::tile::{Content, Tile};
+use crate::helpers_functions::respective_container;
+use crate::CONFIG;
+use crate::container_registry::ContainerRegistry;
+use crate::market::{Markets, COIN_VALUE};
+
+
+// Set it to false if we only want to go to one point at a time.
+pub static TOUR_PLANNING:bool=true;
+
+// We don't plan a tour for less stops than this (only the stops we have a reason to visit now, see useful_stops).
+pub static TOUR_MIN_STOPS:usize=2;
+
+// How many stops we consider at most (the closest ones), so the search stays fast.
+static TOUR_MAX_STOPS:usize=12;
+
+// How far from the robot we search the contents to collect.
+static TOUR_RADIUS:usize=16;
+
+static TOUR_POPULATION:usize=30;
+
+static TOUR_GENERATION_LIMIT:usize=200;
+
+// Energy we expect to spend for every tile of a leg (the path finder will tell us the real one).
+static TOUR_TILE_COST:usize=3;
+
+// Rewards and penalty used in the weight of a tour.
+static COLLECT_REWARD:usize=40;
+static DEPOSIT_REWARD:usize=60;
+static SKIP_PENALTY:usize=30;
+
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum StopKind{
+    // Content we destroy to put it in the backpack
+    Collect(Content),
+    // Container where we put what we have, with the capacity we estimate
+    Deposit(Content,usize),
+    // Market where we sell what the policy says
+    Sell,
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct TourStop{
+    pub(crate) position:(usize,usize),
+    pub(crate) kind:StopKind,
+}
+
+
+// The stops of a tour we can really do, in order.
+// For the deposits we also say what we put and how much.
+#[derive(Debug,Clone,PartialEq)]
+pub struct TourVisit{
+    pub(crate) stop:usize,
+    pub(crate) put:Option<(Content,usize)>,
+}
+
+struct TourEvaluation{
+    visits:Vec<TourVisit>,
+    weight:i32,
+}
+
+
+// The problem we solve: the order of the stops, starting from "start".
+// We can't collect more than the free space of the backpack, and we can't spend more than the budget.
+pub struct TourProblem{
+    start:(usize,usize),
+    pub(crate) stops:Vec<TourStop>,
+    free_space:usize,
+    // What we have in the backpack (keys are the default contents)
+    load:HashMap<Content,usize>,
+    budget:usize,
+    // Coins we expect for one element sold in a market
+    prices:HashMap<Content,f32>,
+}
+
+impl TourProblem{
+
+    pub(crate) fn new(start:(usize,usize),stops:Vec<TourStop>,free_space:usize,load:HashMap<Content,usize>,budget:usize)->Self{
+        TourProblem{
+            start,
+            stops,
+            free_space,
+            load,
+            budget,
+            prices:HashMap::new(),
+        }
+    }
+
+    pub(crate) fn with_prices(mut self,prices:HashMap<Content,f32>)->Self{
+        self.prices=prices;
+        self
+    }
+
+    // The stops we have a reason to visit now: all the contents to collect, the containers for what
+    // we have (or will collect), and the markets if we have something to sell.
+    pub(crate) fn useful_stops(&self)->usize{
+        let carried:Vec<Content>=self.load.iter().filter(|(_,q)| **q>0).map(|(c,_)| c.clone())
+            .chain(self.stops.iter().filter_map(|s| match &s.kind{StopKind::Collect(c) => Some(c.to_default()),_ => Option::None}))
+            .collect();
+
+        self.stops.iter().filter(|s| match &s.kind{
+            StopKind::Collect(_) => true,
+            StopKind::Deposit(container,_) => carried.iter().any(|c| respective_container(c).to_default()==container.to_default()),
+            StopKind::Sell => carried.iter().any(|c| CONFIG.policy.sells(c)),
+        }).count()
+    }
+
+    fn leg_cost(from:(usize,usize),to:(usize,usize))->usize{
+        (from.0.abs_diff(to.0)+from.1.abs_diff(to.1))*TOUR_TILE_COST
+    }
+
+    // We follow the order of the genome. The stops we can't do (backpack full, nothing to deposit)
+    // are skipped and penalized, and we stop when we finish the budget.
+    fn evaluate(&self,genome:&[usize])->TourEvaluation{
+        let mut visits=Vec::new();
+        let mut load=self.load.clone();
+        let mut free_space=self.free_space;
+        let mut position=self.start;
+        let mut cost=0;
+        let mut reward=0;
+        let mut skipped=0;
+
+        for &i in genome.iter(){
+            let stop=&self.stops[i];
+            let leg=TourProblem::leg_cost(position,stop.position);
+
+            match &stop.kind{
+                StopKind::Collect(content) => {
+                    let quantity=content_quantity(content);
+                    if quantity>free_space{skipped+=1;continue}
+
+                    let action_cost=leg+content.properties().cost();
+                    if cost+action_cost>self.budget{break}
+
+                    cost+=action_cost;
+                    free_space-=quantity;
+                    *load.entry(content.to_default()).or_insert(0)+=quantity;
+                    reward+=COLLECT_REWARD+quantity;
+                    visits.push(TourVisit{stop:i,put:Option::None});
+                }
+                StopKind::Deposit(container,capacity) => {
+                    //We put in the container everything of its kind, if it fits
+                    let kind=load.keys().find(|c| respective_container(c).to_default()==container.to_default()).cloned();
+                    let (kind,have)=match kind{
+                        Some(k) => {let have=load[&k]; (k,have)}
+                        Option::None => {skipped+=1;continue}
+                    };
+
+                    let quantity=have.min(*capacity);
+                    if quantity==0{skipped+=1;continue}
+
+                    let action_cost=leg+kind.properties().cost()*quantity;
+                    if cost+action_cost>self.budget{break}
+
+                    cost+=action_cost;
+                    free_space+=quantity;
+                    load.insert(kind.clone(),have-quantity);
+                    reward+=DEPOSIT_REWARD+quantity;
+                    visits.push(TourVisit{stop:i,put:Some((kind,quantity))});
+                }
+                StopKind::Sell => {
+                    //We sell what we have the most of
+                    let kind=load.iter().filter(|(c,q)| **q>0 && CONFIG.policy.sells(c)).max_by_key(|(_,q)| **q).map(|(c,q)| (c.clone(),*q));
+                    let (kind,have)=match kind{
+                        Some(k) => k,
+                        Option::None => {skipped+=1;continue}
+                    };
+
+                    let coins=(*self.prices.get(&kind).unwrap_or(&0.0)*have as f32) as usize;
+                    let action_cost=leg+kind.properties().cost()*have;
+
+                    //The detour isn't worth it, we just don't go there
+                    if coins*COIN_VALUE<=action_cost{continue}
+                    if cost+action_cost>self.budget{break}
+
+                    cost+=action_cost;
+                    free_space=(free_space+have).saturating_sub(coins);
+                    load.insert(kind.clone(),0);
+                    *load.entry(Content::Coin(0)).or_insert(0)+=coins;
+                    reward+=coins*COIN_VALUE;
+                    visits.push(TourVisit{stop:i,put:Some((kind,have))});
+                }
+            }
+
+            position=stop.position;
+        }
+
+        TourEvaluation{
+            visits,
+            weight:cost as i32-reward as i32+(skipped*SKIP_PENALTY) as i32,
+        }
+    }
+
+    // Genetic algorithm over the permutations of the stops.
+    pub(crate) fn solve(&self)->Vec<TourVisit>{
+        let n=self.stops.len();
+        if n==0{return Vec::new()}
+
+        let mut rng=planner_rng();
+
+        let mut population:Vec<Vec<usize>>=Vec::new();
+
+        //The first tour is the nearest neighbour one, the others are random.
+        population.push(self.nearest_neighbour());
+        while population.len()<TOUR_POPULATION{
+            let mut genome:Vec<usize>=(0..n).collect();
+            for i in (1..n).rev(){
+                genome.swap(i,rng.gen_range(0..=i));
+            }
+            population.push(genome);
+        }
+
+        for _ in 0..TOUR_GENERATION_LIMIT{
+            population.sort_by_key(|g| self.evaluate(g).weight);
+
+            //We keep the best half, and the other half are their children.
+            let elite=TOUR_POPULATION/2;
+            population.truncate(elite);
+
+            while population.len()<TOUR_POPULATION{
+                let first=population[rng.gen_range(0..elite)].clone();
+                let second=population[rng.gen_range(0..elite)].clone();
+
+                let mut child=order_crossover(&first,&second);
+
+                if rng.gen_range(0..2)==0{
+                    swap_mutation(&mut child);
+                }else{
+                    two_opt_mutation(&mut child);
+                }
+                population.push(child);
+            }
+        }
+
+        population.sort_by_key(|g| self.evaluate(g).weight);
+        self.evaluate(&population[0]).visits
+    }
+
+    // The visits of a tour in this order. If we can't do a stop, we remove it and we call it again,
+    // so the loads of the next stops don't count what we would have done there.
+    pub(crate) fn visits(&self,order:&[usize])->Vec<TourVisit>{
+        self.evaluate(order).visits
+    }
+
+    fn nearest_neighbour(&self)->Vec<usize>{
+        let mut remaining:Vec<usize>=(0..self.stops.len()).collect();
+        let mut result=Vec::new();
+        let mut position=self.start;
+
+        while !remaining.is_empty(){
+            let (index,_)=remaining.iter().enumerate()
+                .min_by_key(|(_,i)| TourProblem::leg_cost(position,self.stops[**i].position))
+                .unwrap();
+            let stop=remaining.remove(index);
+            position=self.stops[stop].position;
+            result.push(stop);
+        }
+        result
+    }
+}
+
+
+// Order crossover: we copy a slice of the first parent, and the rest in the order of the second one.
+fn order_crossover(first:&Vec<usize>,second:&Vec<usize>)->Vec<usize>{
+    let n=first.len();
+    if n<2{return first.clone()}
+
+    let mut rng=planner_rng();
+    let a=rng.gen_range(0..n);
+    let b=rng.gen_range(a..n);
+
+    let slice=&first[a..=b];
+    let mut rest=second.iter().filter(|g| !slice.contains(g));
+
+    let mut child=Vec::with_capacity(n);
+    for i in 0..n{
+        if i>=a && i<=b{
+            child.push(first[i]);
+        }else{
+            child.push(*rest.next().unwrap());
+        }
+    }
+    child
+}
+
+fn swap_mutation(genome:&mut Vec<usize>){
+    if genome.len()<2{return}
+
+    let mut rng=planner_rng();
+    let a=rng.gen_range(0..genome.len());
+    let b=rng.gen_range(0..genome.len());
+    genome.swap(a,b);
+}
+
+// We reverse a piece of the tour, so two crossing legs become parallel.
+fn two_opt_mutation(genome:&mut Vec<usize>){
+    if genome.len()<2{return}
+
+    let mut rng=planner_rng();
+    let a=rng.gen_range(0..genome.len());
+    let b=rng.gen_range(a..genome.len());
+    genome[a..=b].reverse();
+}
+
+
+// How many elements we get destroying the content.
+pub(crate) fn content_quantity(content:&Content)->usize{
+    match content{
+        Content::Coin(n) | Content::Garbage(n) | Content::Tree(n) | Content::Rock(n) | Content::Fish(n) | Content::Bush(n) | Content::Water(n) => *n,
+        _ => 0,
+    }
+}
+
+// The stops around us: the contents we want to collect and the containers we know.
+// "wants" says which contents we collect.
+pub(crate) fn tour_stops(map:&Vec<Vec<Option<Tile>>>,containers:&ContainerRegistry,markets:&Markets,x:usize,y:usize,wants:&dyn Fn(&Content)->bool,excluded:&dyn Fn((usize,usize))->bool)->Vec<TourStop>{
+    let mut stops=Vec::new();
+
+    let from_x=x.saturating_sub(TOUR_RADIUS);
+    let from_y=y.saturating_sub(TOUR_RADIUS);
+    let to_x=(x+TOUR_RADIUS).min(map.len()-1);
+    let to_y=(y+TOUR_RADIUS).min(map.len()-1);
+
+    for i in from_x..=to_x{
+        for j in from_y..=to_y{
+            if (i,j)==(x,y) || excluded((i,j)){continue}
+
+            match &map[i][j]{
+                Some(tile) if wants(&tile.content) && content_quantity(&tile.content)>0 => {
+                    stops.push(TourStop{position:(i,j),kind:StopKind::Collect(tile.content.clone())});
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (position,container) in containers.usable(){
+        if CONFIG.policy.is_target_container(&container.kind){
+            stops.push(TourStop{position:*position,kind:StopKind::Deposit(container.kind.clone(),container.capacity)});
+        }
+    }
+
+    for position in markets.positions(){
+        stops.push(TourStop{position:*position,kind:StopKind::Sell});
+    }
+
+    stops.sort_by_key(|s| s.position.0.abs_diff(x)+s.position.1.abs_diff(y));
+    stops.truncate(TOUR_MAX_STOPS);
+    stops
+}
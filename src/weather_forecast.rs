@@ -0,0 +1,207 @@
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::Tile;
+use crate::genetic_algorithm::Action;
+use crate::simulator::Simulator;
+
+
+// How many ticks in the future we predict.
+pub static FORECAST_HORIZON:usize=48;
+
+// We wait only if moving now costs more than this ratio of moving later.
+pub static WAIT_RATIO:f32=1.5;
+
+// Set it to false if we never want to wait for a better weather.
+pub static WAIT_FOR_WEATHER:bool=true;
+
+
+// We can't read the forecast of the world, so we learn it from what we observe:
+// how many minutes every tick moves the clock, and the sequence of the weather of the days.
+pub struct Forecast{
+    tick:usize,
+    // (tick, minutes of the day) of the last time we saw the clock
+    last_time:Option<(usize,u32)>,
+    minutes_per_tick:Option<u32>,
+    // Weather of every day we have seen
+    days:Vec<WeatherType>,
+    current:Option<EnvironmentalConditions>,
+}
+
+impl Default for Forecast{
+    fn default() -> Self {
+        Forecast{
+            tick:0,
+            last_time:Option::None,
+            minutes_per_tick:Option::None,
+            days:Vec::new(),
+            current:Option::None,
+        }
+    }
+}
+
+impl Forecast{
+
+    pub(crate) fn next_tick(&mut self){
+        self.tick+=1;
+    }
+
+    // new_day is true when the event was a DayChanged.
+    pub(crate) fn observe(&mut self,environment:&EnvironmentalConditions,new_day:bool){
+        let minutes=parse_minutes(&environment.get_time_of_day_string());
+
+        if let (Some(m),Some((last_tick,last_minutes)))=(minutes,self.last_time){
+            if self.tick>last_tick{
+                //If the clock went back we passed the midnight
+                let passed=if m>=last_minutes{m-last_minutes}else{m+24*60-last_minutes};
+                self.minutes_per_tick=Some(passed/(self.tick-last_tick) as u32);
+            }
+        }
+
+        if let Some(m)=minutes{
+            self.last_time=Some((self.tick,m));
+        }
+
+        if new_day || self.days.is_empty(){
+            self.days.push(environment.get_weather_condition());
+        }
+
+        self.current=Some(environment.clone());
+    }
+
+    // The weather of the days (one period, if we know it) and the minutes for every tick.
+    pub(crate) fn weather_sequence(&self)->(Vec<WeatherType>,u32){
+        let days=match self.period(){
+            Some(p) => self.days[..p].to_vec(),
+            Option::None => self.days.clone(),
+        };
+        (days,self.minutes_per_tick.unwrap_or(0))
+    }
+
+    // Smallest period of the weather sequence, if we have seen it at least two times.
+    fn period(&self)->Option<usize>{
+        for p in 1..=self.days.len()/2{
+            if (0..self.days.len()-p).all(|i| self.days[i]==self.days[i+p]){
+                return Some(p);
+            }
+        }
+        Option::None
+    }
+
+    fn weather_in_days(&self,days:usize)->Option<WeatherType>{
+        let current=self.current.as_ref()?.get_weather_condition();
+        if days==0{return Some(current)}
+
+        match self.period(){
+            Some(p) => {
+                //The last day we have seen is the current one, and the sequence repeats every p days
+                let index=self.days.len()-1+days;
+                Some(self.days[index%p].clone())
+            }
+            //We don't know the sequence, so we say it doesn't change.
+            Option::None => Some(current),
+        }
+    }
+
+    // Conditions predicted after "offset" ticks
+    pub(crate) fn conditions_at(&self,offset:usize)->Option<EnvironmentalConditions>{
+        let current=self.current.as_ref()?;
+        let progression=self.minutes_per_tick.unwrap_or(0);
+
+        let now=match self.last_time{
+            Some((tick,m)) => m+(self.tick-tick) as u32*progression,
+            Option::None => parse_minutes(&current.get_time_of_day_string()).unwrap_or(0),
+        };
+
+        let future=now+offset as u32*progression;
+        let days=(future/(24*60)) as usize;
+        let hour=((future%(24*60))/60) as u8;
+
+        let weather=self.weather_in_days(days)?;
+
+        EnvironmentalConditions::new(&[weather],progression.min(u8::MAX as u32) as u8,hour).ok()
+    }
+
+    // We build the conditions for the threads, so they don't have to do it for every step.
+    pub(crate) fn step_forecast(&self,energy_per_tick:usize)->StepForecast{
+        let mut conditions=Vec::new();
+        for offset in 0..FORECAST_HORIZON{
+            match self.conditions_at(offset){
+                Some(c) => conditions.push(c),
+                Option::None => break,
+            }
+        }
+
+        StepForecast{
+            conditions,
+            energy_per_tick:energy_per_tick.max(1),
+        }
+    }
+}
+
+
+// Conditions for every tick in the future. The robot needs to recharge to walk, so we use the
+// energy spent until a step to know in which tick it will be done.
+#[derive(Clone)]
+pub struct StepForecast{
+    pub(crate) conditions:Vec<EnvironmentalConditions>,
+    pub(crate) energy_per_tick:usize,
+}
+
+impl Default for StepForecast{
+    fn default() -> Self {
+        StepForecast{
+            conditions:Vec::new(),
+            energy_per_tick:1,
+        }
+    }
+}
+
+impl StepForecast{
+
+    pub(crate) fn after_energy(&self,spent:usize,wait:usize)->Option<&EnvironmentalConditions>{
+        if self.conditions.is_empty(){return Option::None}
+
+        let tick=(wait+spent/self.energy_per_tick).min(self.conditions.len()-1);
+        Some(&self.conditions[tick])
+    }
+}
+
+
+// "08:30" -> 510
+fn parse_minutes(time:&str)->Option<u32>{
+    let mut parts=time.trim().split(':');
+    let hours:u32=parts.next()?.trim().parse().ok()?;
+    let minutes:u32=parts.next()?.trim().parse().ok()?;
+    Some(hours*60+minutes)
+}
+
+
+// Cost of the path if we start after "wait" ticks, with the predicted weather of every step.
+pub(crate) fn path_cost_with_forecast(path:&[Action],x:usize,y:usize,map:&Vec<Vec<Option<Tile>>>,forecast:&StepForecast,wait:usize)->Option<usize>{
+    if forecast.conditions.is_empty(){return Option::None}
+
+    Some(Simulator::new(map).with_forecast(forecast).replay(path,(x,y),wait).total_cost())
+}
+
+// How many ticks we should wait before starting the path, if the weather later makes it much cheaper.
+pub(crate) fn best_wait(path:&[Action],x:usize,y:usize,map:&Vec<Vec<Option<Tile>>>,forecast:&StepForecast)->usize{
+    if !WAIT_FOR_WEATHER{return 0}
+
+    let now=match path_cost_with_forecast(path,x,y,map,forecast,0){
+        Some(c) => c,
+        Option::None => return 0,
+    };
+
+    let mut best=(0,now);
+    for wait in 1..forecast.conditions.len(){
+        match path_cost_with_forecast(path,x,y,map,forecast,wait){
+            Some(c) if c<best.1 => best=(wait,c),
+            _ => {}
+        }
+    }
+
+    if now as f32>best.1 as f32*WAIT_RATIO{
+        best.0
+    }else{
+        0
+    }
+}
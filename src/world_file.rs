@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::fs;
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use robotics_lib::world::world_generator::Generator;
+
+
+static HEADER:&str="trust_us_world 1";
+
+// Minutes for every tick, when we don't know them.
+pub static DEFAULT_PROGRESSION:u8=15;
+
+// If this file exists, we write the current world in SNAPSHOT_FILE and we delete it ("touch snapshot.request").
+pub static SNAPSHOT_REQUEST:&str="snapshot.request";
+pub static SNAPSHOT_FILE:&str="snapshot.world";
+
+
+// Everything we need to create the same world again.
+// The file is text, one line for every row of tiles:
+//
+//      trust_us_world 1
+//      size 3
+//      spawn 1 2
+//      time 15 8                       (minutes for every tick, starting hour)
+//      weather Sunny Rainy             (weather of the days, it repeats)
+//      score 100                       (max score)
+//      score_table Coin(0)=1 Tree(0)=2 (optional)
+//      tiles
+//      Grass:0 Grass:1:Coin(3) Sand:0
+//      ...
+//
+// Every tile is "type:elevation" or "type:elevation:content".
+#[derive(Debug,Clone)]
+pub struct WorldFile{
+    pub(crate) tiles:Vec<Vec<Tile>>,
+    pub(crate) spawn:(usize,usize),
+    pub(crate) progression:u8,
+    pub(crate) hour:u8,
+    pub(crate) weather:Vec<WeatherType>,
+    pub(crate) max_score:f32,
+    pub(crate) score_table:Option<HashMap<Content,f32>>,
+}
+
+impl WorldFile{
+
+    // We can only read the weather of today and the hour from the conditions, so the rest must be given.
+    pub(crate) fn new(tiles:Vec<Vec<Tile>>,spawn:(usize,usize),environment:&EnvironmentalConditions,max_score:f32,score_table:Option<HashMap<Content,f32>>)->WorldFile{
+        let hour=environment.get_time_of_day_string().split(':').next().and_then(|h| h.trim().parse().ok()).unwrap_or(8);
+        WorldFile{
+            tiles,
+            spawn,
+            progression:DEFAULT_PROGRESSION,
+            hour,
+            weather:vec![environment.get_weather_condition()],
+            max_score,
+            score_table,
+        }
+    }
+
+    pub(crate) fn with_weather(mut self,weather:Vec<WeatherType>,progression:u8)->WorldFile{
+        if !weather.is_empty(){
+            self.weather=weather;
+        }
+        if progression>0{
+            self.progression=progression;
+        }
+        self
+    }
+
+    // The conditions of the first tick. parse checks them, so the generator can use them.
+    pub(crate) fn environment(&self)->Result<EnvironmentalConditions,String>{
+        if self.hour>23{
+            return Err(format!("the hour {} isn't between 0 and 23",self.hour));
+        }
+        if self.progression==0{
+            return Err(String::from("the minutes for every tick must be more than 0"));
+        }
+        if self.weather.is_empty(){
+            return Err(String::from("no weather"));
+        }
+        EnvironmentalConditions::new(&self.weather,self.progression,self.hour).map_err(|e| format!("wrong time or weather:{:?}",e))
+    }
+
+    pub(crate) fn save(&self,filename:&str)->Result<(),String>{
+        fs::write(filename,self.to_text()).map_err(|e| format!("{}: {}",filename,e))
+    }
+
+    pub(crate) fn to_text(&self)->String{
+        let mut s=String::new();
+        s.push_str(&format!("{}\nsize {}\nspawn {} {}\ntime {} {}\nweather",HEADER,self.tiles.len(),self.spawn.0,self.spawn.1,self.progression,self.hour));
+        for w in self.weather.iter(){
+            s.push_str(&format!(" {:?}",w));
+        }
+        s.push_str(&format!("\nscore {}\n",self.max_score));
+
+        if let Some(table)=&self.score_table{
+            //Always in the same order, so the same world gives the same file
+            let mut entries:Vec<String>=table.iter().map(|(content,score)| format!("{:?}={}",content,score)).collect();
+            entries.sort();
+
+            s.push_str("score_table");
+            for entry in entries.iter(){
+                s.push_str(&format!(" {}",entry));
+            }
+            s.push('\n');
+        }
+
+        s.push_str("tiles\n");
+        for row in self.tiles.iter(){
+            let line:Vec<String>=row.iter().map(tile_to_string).collect();
+            s.push_str(&line.join(" "));
+            s.push('\n');
+        }
+        s
+    }
+
+    pub(crate) fn load(filename:&str)->Result<WorldFile,String>{
+        let text=fs::read_to_string(filename).map_err(|e| format!("{}: {}",filename,e))?;
+        WorldFile::parse(&text)
+    }
+
+    pub(crate) fn parse(text:&str)->Result<WorldFile,String>{
+        let mut lines=text.lines();
+        if lines.next().map(|l| l.trim())!=Some(HEADER){
+            return Err(String::from("this is not a world file (or the version is different)"));
+        }
+
+        let mut world=WorldFile{
+            tiles:Vec::new(),
+            spawn:(0,0),
+            progression:DEFAULT_PROGRESSION,
+            hour:8,
+            weather:vec![WeatherType::Sunny],
+            max_score:0.0,
+            score_table:Option::None,
+        };
+        let mut size=0;
+
+        for line in lines.by_ref(){
+            let mut parts=line.split_whitespace();
+            match parts.next(){
+                Some("tiles") => break,
+                Some("size") => size=number(parts.next())?,
+                Some("spawn") => world.spawn=(number(parts.next())?,number(parts.next())?),
+                Some("time") => {
+                    world.progression=number(parts.next())?;
+                    world.hour=number(parts.next())?;
+                }
+                Some("weather") => world.weather=parts.map(parse_weather).collect::<Result<_,_>>()?,
+                Some("score") => world.max_score=number(parts.next())?,
+                Some("score_table") => {
+                    let mut table=HashMap::new();
+                    for entry in parts{
+                        let (content,score)=entry.split_once('=').ok_or(format!("wrong score \"{}\"",entry))?;
+                        table.insert(parse_content_value(content)?,number(Some(score))?);
+                    }
+                    world.score_table=Some(table);
+                }
+                Some(other) => return Err(format!("unknown line \"{}\"",other)),
+                Option::None => {}
+            }
+        }
+
+        for (n,line) in lines.enumerate(){
+            if line.trim().is_empty(){continue}
+            let row=line.split_whitespace().map(parse_tile).collect::<Result<Vec<Tile>,String>>()
+                .map_err(|e| format!("row {}: {}",n,e))?;
+            if row.len()!=size{
+                return Err(format!("row {} has {} tiles instead of {}",n,row.len(),size));
+            }
+            world.tiles.push(row);
+        }
+
+        if world.tiles.len()!=size{
+            return Err(format!("{} rows instead of {}",world.tiles.len(),size));
+        }
+        if world.spawn.0>=size || world.spawn.1>=size{
+            return Err(String::from("the spawn is outside the world"));
+        }
+        world.environment()?;
+        Ok(world)
+    }
+}
+
+
+// The runner can use a world file like the other generators.
+impl Generator for WorldFile{
+    fn gen(&mut self)->(Vec<Vec<Tile>>,(usize,usize),EnvironmentalConditions,f32,Option<HashMap<Content,f32>>){
+        let environment=self.environment().expect("the world was checked when we created it");
+        (self.tiles.clone(),self.spawn,environment,self.max_score,self.score_table.clone())
+    }
+}
+
+
+pub(crate) fn number<T:std::str::FromStr>(value:Option<&str>)->Result<T,String>{
+    let value=value.ok_or(String::from("missing value"))?;
+    value.parse().map_err(|_| format!("\"{}\" is not a number",value))
+}
+
+pub(crate) fn tile_to_string(tile:&Tile)->String{
+    if tile.content==Content::None{
+        format!("{:?}:{}",tile.tile_type,tile.elevation)
+    }else{
+        format!("{:?}:{}:{:?}",tile.tile_type,tile.elevation,tile.content)
+    }
+}
+
+pub(crate) fn parse_tile(text:&str)->Result<Tile,String>{
+    let mut parts=text.splitn(3,':');
+    let tile_type=parse_tile_type(parts.next().unwrap_or(""))?;
+    let elevation=number(parts.next())?;
+    let content=match parts.next(){
+        Some(c) => parse_content_value(c)?,
+        Option::None => Content::None,
+    };
+    Ok(Tile{tile_type,content,elevation})
+}
+
+pub(crate) fn parse_tile_type(text:&str)->Result<TileType,String>{
+    match text{
+        "DeepWater" => Ok(TileType::DeepWater),
+        "ShallowWater" => Ok(TileType::ShallowWater),
+        "Sand" => Ok(TileType::Sand),
+        "Grass" => Ok(TileType::Grass),
+        "Street" => Ok(TileType::Street),
+        "Hill" => Ok(TileType::Hill),
+        "Mountain" => Ok(TileType::Mountain),
+        "Snow" => Ok(TileType::Snow),
+        "Lava" => Ok(TileType::Lava),
+        "Wall" => Ok(TileType::Wall),
+        "Teleport(true)" => Ok(TileType::Teleport(true)),
+        "Teleport(false)" => Ok(TileType::Teleport(false)),
+        other => Err(format!("unknown tile \"{}\"",other)),
+    }
+}
+
+// Contents written like "Coin(3)", "Bin(0..10)" or "Fire".
+pub(crate) fn parse_content_value(text:&str)->Result<Content,String>{
+    let (name,value)=match text.split_once('('){
+        Some((name,rest)) => (name,rest.strip_suffix(')').ok_or(format!("wrong content \"{}\"",text))?),
+        Option::None => (text,""),
+    };
+
+    let range=||->Result<std::ops::Range<usize>,String>{
+        let (a,b)=value.split_once("..").ok_or(format!("wrong range \"{}\"",value))?;
+        Ok(number(Some(a))?..number(Some(b))?)
+    };
+
+    match name{
+        "Rock" => Ok(Content::Rock(number(Some(value))?)),
+        "Tree" => Ok(Content::Tree(number(Some(value))?)),
+        "Garbage" => Ok(Content::Garbage(number(Some(value))?)),
+        "Fire" => Ok(Content::Fire),
+        "Coin" => Ok(Content::Coin(number(Some(value))?)),
+        "Bin" => Ok(Content::Bin(range()?)),
+        "Crate" => Ok(Content::Crate(range()?)),
+        "Bank" => Ok(Content::Bank(range()?)),
+        "Water" => Ok(Content::Water(number(Some(value))?)),
+        "Market" => Ok(Content::Market(number(Some(value))?)),
+        "Fish" => Ok(Content::Fish(number(Some(value))?)),
+        "Building" => Ok(Content::Building),
+        "Bush" => Ok(Content::Bush(number(Some(value))?)),
+        "JollyBlock" => Ok(Content::JollyBlock(number(Some(value))?)),
+        "Scarecrow" => Ok(Content::Scarecrow),
+        "None" => Ok(Content::None),
+        other => Err(format!("unknown content \"{}\"",other)),
+    }
+}
+
+fn parse_weather(text:&str)->Result<WeatherType,String>{
+    match text{
+        "Sunny" => Ok(WeatherType::Sunny),
+        "Rainy" => Ok(WeatherType::Rainy),
+        "Foggy" => Ok(WeatherType::Foggy),
+        "TropicalMonsoon" => Ok(WeatherType::TropicalMonsoon),
+        "TrentinoSnow" => Ok(WeatherType::TrentinoSnow),
+        other => Err(format!("unknown weather \"{}\"",other)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests{
+    use std::collections::HashMap;
+    use robotics_lib::world::environmental_conditions::WeatherType;
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+    use super::WorldFile;
+
+    // Every tile type and every content, at least once.
+    fn world()->WorldFile{
+        let types=[TileType::DeepWater,TileType::ShallowWater,TileType::Sand,TileType::Grass,TileType::Street,TileType::Hill,
+            TileType::Mountain,TileType::Snow,TileType::Lava,TileType::Wall,TileType::Teleport(true),TileType::Teleport(false)];
+        let contents=[Content::Rock(1),Content::Tree(2),Content::Garbage(3),Content::Fire,Content::Coin(4),Content::Bin(0..5),
+            Content::Crate(1..6),Content::Bank(0..7),Content::Water(8),Content::Market(9),Content::Fish(10),Content::Building,
+            Content::Bush(11),Content::JollyBlock(12),Content::Scarecrow,Content::None];
+
+        let size=4;
+        let tiles=(0..size).map(|i| (0..size).map(|j|{
+            let k=i*size+j;
+            Tile{tile_type:types[k%types.len()].clone(),content:contents[k%contents.len()].clone(),elevation:k}
+        }).collect()).collect();
+
+        WorldFile{
+            tiles,
+            spawn:(1,2),
+            progression:20,
+            hour:7,
+            weather:vec![WeatherType::Rainy,WeatherType::Sunny,WeatherType::TrentinoSnow],
+            max_score:123.5,
+            score_table:Some(HashMap::from([(Content::Coin(0),1.5),(Content::Tree(0),2.0),(Content::Garbage(0),0.5),(Content::Fish(0),3.0)])),
+        }
+    }
+
+    #[test]
+    fn a_saved_world_is_parsed_back_the_same(){
+        let world=world();
+        let text=world.to_text();
+        let parsed=WorldFile::parse(&text).unwrap();
+
+        for (row,parsed_row) in world.tiles.iter().zip(parsed.tiles.iter()){
+            for (tile,parsed_tile) in row.iter().zip(parsed_row.iter()){
+                assert_eq!(tile.tile_type,parsed_tile.tile_type);
+                assert_eq!(tile.content,parsed_tile.content);
+                assert_eq!(tile.elevation,parsed_tile.elevation);
+            }
+        }
+        assert_eq!(parsed.tiles.len(),world.tiles.len());
+        assert_eq!(parsed.spawn,world.spawn);
+        assert_eq!((parsed.progression,parsed.hour),(world.progression,world.hour));
+        assert_eq!(parsed.weather,world.weather);
+        assert_eq!(parsed.max_score,world.max_score);
+        assert_eq!(parsed.score_table,world.score_table);
+
+        //The same world always gives the same file
+        assert_eq!(parsed.to_text(),text);
+    }
+
+    #[test]
+    fn wrong_worlds_are_rejected(){
+        let text=world().to_text();
+
+        assert!(WorldFile::parse(&text.replacen("spawn 1 2","spawn 9 2",1)).is_err());
+        assert!(WorldFile::parse(&text.replacen("time 20 7","time 20 30",1)).is_err());
+        assert!(WorldFile::parse(&text.replacen("Grass","Gras",1)).is_err());
+        assert!(WorldFile::parse(&text.replacen("trust_us_world 1","trust_us_world 2",1)).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use ghost_amazeing_island::world_generator::WorldGenerator;
+use crate::config::parse_number;
+use crate::world_file::WorldFile;
+use crate::ascii_world::AsciiWorld;
+
+
+// The generators we can use.
+// "file" loads a world saved with world_file, "ascii" a world written by hand (see ascii_world).
+static GENERATORS:[&str;3]=["ghost","file","ascii"];
+
+// Crates of the dependencies that were asked as generators, but they only have tools for the robot
+// (nothing in them implements Generator). Choosing them stops the run, we don't create another world in their place.
+static TOOL_CRATES:[&str;2]=["crab_rave_explorer","bob_lib"];
+
+
+// Which generator creates the world, with its parameters.
+// In the configuration file:
+//
+//      world.generator=ghost
+//      world.size=200
+//      world.random_seed=false
+//      world.seed=42
+//      world.variation=0.1
+//      world.file=bug.world            (with world.generator=file or ascii)
+//      world.snapshot=last.world       (we save the world we create, to use it again)
+//
+// or from the command line: "--generator ghost --size 200 --seed 42", "--world-file bug.world", "--snapshot last.world".
+// "--world-file" also selects the "file" generator.
+// The snapshot of a ghost world only has the weather of the first day (the generator doesn't tell us the next ones),
+// so the world is the same but the weather of the next days isn't. The snapshot the robot writes during the run
+// (see SNAPSHOT_REQUEST) has the weather it has seen instead.
+#[derive(Debug,Clone,PartialEq)]
+pub struct WorldSource{
+    pub(crate) generator:String,
+    pub(crate) size:usize,
+    pub(crate) random_seed:bool,
+    pub(crate) seed:u64,
+    pub(crate) variation:f64,
+    pub(crate) file:Option<String>,
+    pub(crate) snapshot:Option<String>,
+}
+
+impl Default for WorldSource{
+    // The world we always used.
+    fn default() -> Self {
+        WorldSource{
+            generator:String::from("ghost"),
+            size:500,
+            random_seed:true,
+            seed:0,
+            variation:0.1,
+            file:Option::None,
+            snapshot:Option::None,
+        }
+    }
+}
+
+impl WorldSource{
+
+    // "key" is without "world.".
+    pub(crate) fn set(&mut self,key:&str,value:&str)->Result<(),String>{
+        match key{
+            "generator" => {
+                //We keep it anyway: if it's wrong, generator() stops the run instead of using the default world
+                self.generator=value.to_string();
+                if TOOL_CRATES.contains(&value){
+                    return Err(format!("{} is a tool for the robot, it doesn't create worlds (we have {:?})",value,GENERATORS));
+                }
+                if !GENERATORS.contains(&value){
+                    return Err(format!("unknown generator \"{}\" (we have {:?})",value,GENERATORS));
+                }
+            }
+            "size" => self.size=parse_number(value)?,
+            "random_seed" => self.random_seed=value.parse().map_err(|_| format!("\"{}\" is not true or false",value))?,
+            "seed" => {
+                self.seed=parse_number(value)? as u64;
+                self.random_seed=false;
+            }
+            "variation" => self.variation=value.parse().map_err(|_| format!("\"{}\" is not a number",value))?,
+            "file" => self.file=Some(value.to_string()),
+            "snapshot" => self.snapshot=Some(value.to_string()),
+            _ => return Err(format!("unknown key \"world.{}\"",key)),
+        }
+        Ok(())
+    }
+
+    // The command line wins over the configuration file.
+    pub(crate) fn apply_args(&mut self,args:&[String]){
+        if args.iter().any(|a| a=="--world-file"){
+            self.generator=String::from("file");
+        }
+
+        for (flag,key) in [("--generator","generator"),("--size","size"),("--seed","seed"),("--variation","variation"),("--world-file","file"),("--snapshot","snapshot")]{
+            if let Some(i)=args.iter().position(|a| a==flag){
+                match args.get(i+1){
+                    Some(value) => {
+                        if let Err(e)=self.set(key,value){
+                            println!("Argument {}: {}",flag,e);
+                        }
+                    }
+                    Option::None => println!("Argument {}: missing value",flag),
+                }
+            }
+        }
+    }
+
+    // What identifies the world, so the knowledge of a run is used only in the same world.
+    // None if the world is random.
+    pub(crate) fn fingerprint(&self)->Option<String>{
+        match self.generator.as_str(){
+            "file" | "ascii" => Some(format!("{} {}",self.generator,self.file.as_deref().unwrap_or(""))),
+            _ if self.random_seed => Option::None,
+            _ => Some(format!("{} seed={} size={} variation={}",self.generator,self.seed,self.size,self.variation)),
+        }
+    }
+
+    // The world can't be bigger than the structures of the robot.
+    pub(crate) fn generator(&self,max_size:usize)->Result<WorldGenerators,String>{
+        let size=if self.size>max_size{
+            println!("The world size {} is too big, we use {}",self.size,max_size);
+            max_size
+        }else{
+            self.size
+        };
+
+        let source=match self.generator.as_str(){
+            "file" => {
+                let filename=self.file.as_ref().ok_or(String::from("world.file is missing"))?;
+                let world=WorldFile::load(filename)?;
+                if world.tiles.len()>max_size{
+                    return Err(format!("the world in {} is bigger than {}",filename,max_size));
+                }
+                Source::File(world)
+            }
+            "ascii" => {
+                let filename=self.file.as_ref().ok_or(String::from("world.file is missing"))?;
+                let text=std::fs::read_to_string(filename).map_err(|e| format!("{}: {}",filename,e))?;
+                let world=AsciiWorld::parse(&text)?.world_file()?;
+                world.environment()?;
+                Source::File(world)
+            }
+            "ghost" => Source::Ghost(WorldGenerator::new(size as u32,self.random_seed,self.seed,self.variation)),
+            other if TOOL_CRATES.contains(&other) => return Err(format!("{} is a tool for the robot, it doesn't create worlds (we have {:?})",other,GENERATORS)),
+            other => return Err(format!("unknown generator \"{}\" (we have {:?})",other,GENERATORS)),
+        };
+
+        Ok(WorldGenerators{source,snapshot:self.snapshot.clone(),score:(0.0,Option::None)})
+    }
+}
+
+
+pub enum Source{
+    Ghost(WorldGenerator),
+    File(WorldFile),
+}
+
+
+// The generator chosen with WorldSource. The runner wants a single type, so we wrap them.
+pub struct WorldGenerators{
+    source:Source,
+    // Where we save the world we create
+    snapshot:Option<String>,
+    // The score of the world we created, the robot saves it in its snapshots
+    score:(f32,Option<HashMap<Content,f32>>),
+}
+
+impl WorldGenerators{
+    pub(crate) fn score(&self)->(f32,Option<HashMap<Content,f32>>){
+        self.score.clone()
+    }
+}
+
+impl Generator for WorldGenerators{
+    fn gen(&mut self)->(Vec<Vec<Tile>>,(usize,usize),EnvironmentalConditions,f32,Option<HashMap<Content,f32>>){
+        let world=match &mut self.source{
+            Source::Ghost(g) => g.gen(),
+            Source::File(f) => f.gen(),
+        };
+        self.score=(world.3,world.4.clone());
+
+        if let Some(filename)=&self.snapshot{
+            //The weather of the next days isn't in the conditions, so the snapshot only has the first day
+            let (tiles,spawn,environment,max_score,score_table)=world.clone();
+            let file=match &self.source{
+                Source::File(f) => f.clone(),
+                Source::Ghost(_) => WorldFile::new(tiles,spawn,&environment,max_score,score_table),
+            };
+            match file.save(filename){
+                Ok(_) => println!("World saved in {}",filename),
+                Err(e) => println!("Error saving the world:{}",e),
+            }
+        }
+        world
+    }
+}